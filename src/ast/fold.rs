@@ -0,0 +1,287 @@
+use super::*;
+
+// rewriting walk that takes the tree by value and builds a new one. a statement
+// or expression can be replaced by any other kind, so those methods return the
+// enum, and the fold_ functions rebuild a node from its folded children
+pub trait Folder {
+	fn fold_program(&mut self, n: Program) -> Program {
+		fold_program(self, n)
+	}
+	fn fold_block(&mut self, n: BlockStatement) -> BlockStatement {
+		fold_block(self, n)
+	}
+	fn fold_stmt(&mut self, n: Stmt) -> Stmt {
+		fold_stmt(self, n)
+	}
+	fn fold_expr(&mut self, n: Expr) -> Expr {
+		fold_expr(self, n)
+	}
+	// names that are declared or assigned: let, assignment, parameters and loop variables
+	fn fold_identifier(&mut self, n: Identifier) -> Identifier {
+		n
+	}
+	// a name that is read, which can be replaced by any expression
+	fn fold_identifier_expr(&mut self, n: Identifier) -> Expr {
+		Expr::Identifier(n)
+	}
+
+	fn fold_let(&mut self, n: LetStatement) -> Stmt {
+		Stmt::Let(fold_let(self, n))
+	}
+	fn fold_return(&mut self, n: ReturnStatement) -> Stmt {
+		Stmt::Return(fold_return(self, n))
+	}
+	fn fold_expression_stmt(&mut self, n: ExpressionStatement) -> Stmt {
+		Stmt::Expression(fold_expression_stmt(self, n))
+	}
+	fn fold_while(&mut self, n: WhileStatement) -> Stmt {
+		Stmt::While(fold_while(self, n))
+	}
+	fn fold_for(&mut self, n: ForStatement) -> Stmt {
+		Stmt::For(fold_for(self, n))
+	}
+	fn fold_break(&mut self, n: BreakStatement) -> Stmt {
+		Stmt::Break(n)
+	}
+	fn fold_continue(&mut self, n: ContinueStatement) -> Stmt {
+		Stmt::Continue(n)
+	}
+
+	fn fold_integer(&mut self, n: IntegerLiteral) -> Expr {
+		Expr::Integer(n)
+	}
+	fn fold_float(&mut self, n: FloatLiteral) -> Expr {
+		Expr::Float(n)
+	}
+	fn fold_string(&mut self, n: StringLiteral) -> Expr {
+		Expr::String(n)
+	}
+	fn fold_boolean(&mut self, n: BooleanLiteral) -> Expr {
+		Expr::Boolean(n)
+	}
+	fn fold_array(&mut self, n: ArrayLiteral) -> Expr {
+		Expr::Array(fold_array(self, n))
+	}
+	fn fold_hash(&mut self, n: HashLiteral) -> Expr {
+		Expr::Hash(fold_hash(self, n))
+	}
+	fn fold_index(&mut self, n: IndexExpression) -> Expr {
+		Expr::Index(fold_index(self, n))
+	}
+	fn fold_prefix(&mut self, n: PrefixExpression) -> Expr {
+		Expr::Prefix(fold_prefix(self, n))
+	}
+	fn fold_infix(&mut self, n: InfixExpression) -> Expr {
+		Expr::Infix(fold_infix(self, n))
+	}
+	fn fold_logical(&mut self, n: LogicalExpression) -> Expr {
+		Expr::Logical(fold_logical(self, n))
+	}
+	fn fold_assign(&mut self, n: AssignExpression) -> Expr {
+		Expr::Assign(fold_assign(self, n))
+	}
+	fn fold_index_assign(&mut self, n: IndexAssignExpression) -> Expr {
+		Expr::IndexAssign(fold_index_assign(self, n))
+	}
+	fn fold_if(&mut self, n: IfExpression) -> Expr {
+		Expr::If(fold_if(self, n))
+	}
+	fn fold_function(&mut self, n: FunctionLiteral) -> Expr {
+		Expr::Function(fold_function(self, n))
+	}
+	fn fold_call(&mut self, n: CallExpression) -> Expr {
+		Expr::Call(fold_call(self, n))
+	}
+}
+
+fn fold_boxed<F: Folder + ?Sized>(f: &mut F, n: Box<Expr>) -> Box<Expr> {
+	Box::new(f.fold_expr(*n))
+}
+
+pub fn fold_program<F: Folder + ?Sized>(f: &mut F, n: Program) -> Program {
+	Program {
+		statements: n.statements.into_iter().map(|s| f.fold_stmt(s)).collect(),
+		errors: n.errors,
+	}
+}
+
+pub fn fold_block<F: Folder + ?Sized>(f: &mut F, n: BlockStatement) -> BlockStatement {
+	BlockStatement {
+		token: n.token,
+		statements: n.statements.into_iter().map(|s| f.fold_stmt(s)).collect(),
+	}
+}
+
+pub fn fold_stmt<F: Folder + ?Sized>(f: &mut F, n: Stmt) -> Stmt {
+	match n {
+		Stmt::Let(n) => f.fold_let(n),
+		Stmt::Return(n) => f.fold_return(n),
+		Stmt::Expression(n) => f.fold_expression_stmt(n),
+		Stmt::While(n) => f.fold_while(n),
+		Stmt::For(n) => f.fold_for(n),
+		Stmt::Break(n) => f.fold_break(n),
+		Stmt::Continue(n) => f.fold_continue(n),
+	}
+}
+
+pub fn fold_expr<F: Folder + ?Sized>(f: &mut F, n: Expr) -> Expr {
+	match n {
+		Expr::Identifier(n) => f.fold_identifier_expr(n),
+		Expr::Integer(n) => f.fold_integer(n),
+		Expr::Float(n) => f.fold_float(n),
+		Expr::String(n) => f.fold_string(n),
+		Expr::Boolean(n) => f.fold_boolean(n),
+		Expr::Array(n) => f.fold_array(n),
+		Expr::Hash(n) => f.fold_hash(n),
+		Expr::Index(n) => f.fold_index(n),
+		Expr::Prefix(n) => f.fold_prefix(n),
+		Expr::Infix(n) => f.fold_infix(n),
+		Expr::Logical(n) => f.fold_logical(n),
+		Expr::Assign(n) => f.fold_assign(n),
+		Expr::IndexAssign(n) => f.fold_index_assign(n),
+		Expr::If(n) => f.fold_if(n),
+		Expr::Function(n) => f.fold_function(n),
+		Expr::Call(n) => f.fold_call(n),
+	}
+}
+
+pub fn fold_let<F: Folder + ?Sized>(f: &mut F, n: LetStatement) -> LetStatement {
+	LetStatement {
+		token: n.token,
+		name: f.fold_identifier(n.name),
+		value: f.fold_expr(n.value),
+	}
+}
+
+pub fn fold_return<F: Folder + ?Sized>(f: &mut F, n: ReturnStatement) -> ReturnStatement {
+	ReturnStatement {
+		token: n.token,
+		value: f.fold_expr(n.value),
+	}
+}
+
+pub fn fold_expression_stmt<F: Folder + ?Sized>(
+	f: &mut F,
+	n: ExpressionStatement,
+) -> ExpressionStatement {
+	ExpressionStatement {
+		token: n.token,
+		expression: f.fold_expr(n.expression),
+	}
+}
+
+pub fn fold_while<F: Folder + ?Sized>(f: &mut F, n: WhileStatement) -> WhileStatement {
+	WhileStatement {
+		token: n.token,
+		condition: f.fold_expr(n.condition),
+		body: f.fold_block(n.body),
+	}
+}
+
+pub fn fold_for<F: Folder + ?Sized>(f: &mut F, n: ForStatement) -> ForStatement {
+	ForStatement {
+		token: n.token,
+		vars: n.vars.into_iter().map(|i| f.fold_identifier(i)).collect(),
+		iterable: f.fold_expr(n.iterable),
+		body: f.fold_block(n.body),
+	}
+}
+
+pub fn fold_array<F: Folder + ?Sized>(f: &mut F, n: ArrayLiteral) -> ArrayLiteral {
+	ArrayLiteral {
+		tok: n.tok,
+		mems: n.mems.into_iter().map(|m| f.fold_expr(m)).collect(),
+	}
+}
+
+pub fn fold_hash<F: Folder + ?Sized>(f: &mut F, n: HashLiteral) -> HashLiteral {
+	HashLiteral {
+		tok: n.tok,
+		pairs: n
+			.pairs
+			.into_iter()
+			.map(|(k, v)| (f.fold_expr(k), f.fold_expr(v)))
+			.collect(),
+	}
+}
+
+pub fn fold_index<F: Folder + ?Sized>(f: &mut F, n: IndexExpression) -> IndexExpression {
+	IndexExpression {
+		tok: n.tok,
+		left: fold_boxed(f, n.left),
+		index: fold_boxed(f, n.index),
+	}
+}
+
+pub fn fold_prefix<F: Folder + ?Sized>(f: &mut F, n: PrefixExpression) -> PrefixExpression {
+	PrefixExpression {
+		token: n.token,
+		operator: n.operator,
+		right: fold_boxed(f, n.right),
+	}
+}
+
+pub fn fold_infix<F: Folder + ?Sized>(f: &mut F, n: InfixExpression) -> InfixExpression {
+	InfixExpression {
+		token: n.token,
+		left: fold_boxed(f, n.left),
+		operator: n.operator,
+		right: fold_boxed(f, n.right),
+	}
+}
+
+pub fn fold_logical<F: Folder + ?Sized>(f: &mut F, n: LogicalExpression) -> LogicalExpression {
+	LogicalExpression {
+		token: n.token,
+		left: fold_boxed(f, n.left),
+		operator: n.operator,
+		right: fold_boxed(f, n.right),
+	}
+}
+
+pub fn fold_assign<F: Folder + ?Sized>(f: &mut F, n: AssignExpression) -> AssignExpression {
+	AssignExpression {
+		token: n.token,
+		name: f.fold_identifier(n.name),
+		operator: n.operator,
+		value: fold_boxed(f, n.value),
+	}
+}
+
+pub fn fold_index_assign<F: Folder + ?Sized>(
+	f: &mut F,
+	n: IndexAssignExpression,
+) -> IndexAssignExpression {
+	IndexAssignExpression {
+		token: n.token,
+		left: fold_boxed(f, n.left),
+		index: fold_boxed(f, n.index),
+		operator: n.operator,
+		value: fold_boxed(f, n.value),
+	}
+}
+
+pub fn fold_if<F: Folder + ?Sized>(f: &mut F, n: IfExpression) -> IfExpression {
+	IfExpression {
+		token: n.token,
+		condition: fold_boxed(f, n.condition),
+		consequence: f.fold_block(n.consequence),
+		alternative: n.alternative.map(|b| f.fold_block(b)),
+	}
+}
+
+pub fn fold_function<F: Folder + ?Sized>(f: &mut F, n: FunctionLiteral) -> FunctionLiteral {
+	FunctionLiteral {
+		token: n.token,
+		params: n.params.into_iter().map(|p| f.fold_identifier(p)).collect(),
+		body: f.fold_block(n.body),
+	}
+}
+
+pub fn fold_call<F: Folder + ?Sized>(f: &mut F, n: CallExpression) -> CallExpression {
+	CallExpression {
+		token: n.token,
+		function: fold_boxed(f, n.function),
+		args: n.args.into_iter().map(|a| f.fold_expr(a)).collect(),
+	}
+}
@@ -1,87 +1,120 @@
-use super::token::Token;
-use crate::{
-	eval::{Eval, EvalNode},
-	parser::ParserError,
-};
-use std::any::Any;
-use std::collections::HashMap;
-
-pub trait Node: Any {
+pub mod fold;
+pub mod visit;
+
+use super::token::{Token, TokenType};
+use crate::parser::ParserError;
+
+pub trait Node {
 	fn token_literal(&self) -> String;
 	fn string(&self) -> String;
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode>;
-	fn clone_into_node(&self) -> Box<dyn Node>;
 }
-#[derive(Clone)]
-pub struct Program {
-	pub statements: Vec<ResultNode>,
-}
-
-type BoxNode = Box<dyn Node>;
-type ResultNode = Result<Box<dyn Node>, ParserError>;
 
-impl Clone for Box<dyn Node> {
-	fn clone(&self) -> Self {
-		self.clone_into_node()
-	}
+// statements with a syntax error are left out, the parser keeps them in errors
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+	pub statements: Vec<Stmt>,
+	pub errors: Vec<ParserError>,
 }
 impl Node for Program {
 	fn token_literal(&self) -> String {
-		if self.statements.len() > 0 {
-			self.statements[0].token_literal()
-		} else {
-			"".to_string()
+		match self.statements.first() {
+			Some(s) => s.token_literal(),
+			None => "".to_string(),
 		}
 	}
 	fn string(&self) -> String {
 		self.statements
 			.iter()
-			.clone()
 			.fold("".to_string(), |acc, x| acc + "\n" + &x.string())
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
 
-impl Node for ResultNode {
-	fn token_literal(&self) -> String {
+#[derive(Clone, Debug, PartialEq)]
+pub enum Stmt {
+	Let(LetStatement),
+	Return(ReturnStatement),
+	Expression(ExpressionStatement),
+	While(WhileStatement),
+	For(ForStatement),
+	Break(BreakStatement),
+	Continue(ContinueStatement),
+}
+impl Stmt {
+	fn node(&self) -> &dyn Node {
 		match self {
-			Ok(box_node) => box_node.token_literal(),
-			Err(_) => "err".to_string(),
+			Stmt::Let(n) => n,
+			Stmt::Return(n) => n,
+			Stmt::Expression(n) => n,
+			Stmt::While(n) => n,
+			Stmt::For(n) => n,
+			Stmt::Break(n) => n,
+			Stmt::Continue(n) => n,
 		}
 	}
+}
+impl Node for Stmt {
+	fn token_literal(&self) -> String {
+		self.node().token_literal()
+	}
 	fn string(&self) -> String {
+		self.node().string()
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+	Identifier(Identifier),
+	Integer(IntegerLiteral),
+	Float(FloatLiteral),
+	String(StringLiteral),
+	Boolean(BooleanLiteral),
+	Array(ArrayLiteral),
+	Hash(HashLiteral),
+	Index(IndexExpression),
+	Prefix(PrefixExpression),
+	Infix(InfixExpression),
+	Logical(LogicalExpression),
+	Assign(AssignExpression),
+	IndexAssign(IndexAssignExpression),
+	If(IfExpression),
+	Function(FunctionLiteral),
+	Call(CallExpression),
+}
+impl Expr {
+	fn node(&self) -> &dyn Node {
 		match self {
-			Ok(ok_node) => return ok_node.string(),
-			Err(ParserError::UnhandledError) => {
-				panic!("Unexspected Result Encountered")
-			}
-			Err(ParserError::UnexpectedEOF(msg)) => return msg.clone().to_string(),
-			Err(ParserError::IntParseError(msg)) => return msg.clone().to_string(),
-			Err(ParserError::UnexpectedToken(msg)) => return msg.clone().to_string(),
-			Err(ParserError::NoPrefixParseFn(msg)) => return msg.clone().to_string(),
-			Err(ParserError::NoInfixParseFn(msg)) => return msg.clone().to_string(),
+			Expr::Identifier(n) => n,
+			Expr::Integer(n) => n,
+			Expr::Float(n) => n,
+			Expr::String(n) => n,
+			Expr::Boolean(n) => n,
+			Expr::Array(n) => n,
+			Expr::Hash(n) => n,
+			Expr::Index(n) => n,
+			Expr::Prefix(n) => n,
+			Expr::Infix(n) => n,
+			Expr::Logical(n) => n,
+			Expr::Assign(n) => n,
+			Expr::IndexAssign(n) => n,
+			Expr::If(n) => n,
+			Expr::Function(n) => n,
+			Expr::Call(n) => n,
 		}
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		panic!("Unwrap result before passing into eval node")
+}
+impl Node for Expr {
+	fn token_literal(&self) -> String {
+		self.node().token_literal()
 	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		match self {
-			Err(e) => Box::new(Err(e.clone())),
-			Ok(n) => Box::new(Ok(n.clone())),
-		}
+	fn string(&self) -> String {
+		self.node().string()
 	}
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct HashLiteral {
 	pub tok: Token,
-	pub pairs: Vec<(Box<dyn Node>, Box<dyn Node>)>,
+	pub pairs: Vec<(Expr, Expr)>,
 }
 impl Node for HashLiteral {
 	fn token_literal(&self) -> String {
@@ -100,21 +133,13 @@ impl Node for HashLiteral {
 		out += "}";
 		out
 	}
-
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct IndexExpression {
 	pub tok: Token,
-	pub left: Box<dyn Node>,
-	pub index: Box<dyn Node>,
+	pub left: Box<Expr>,
+	pub index: Box<Expr>,
 }
 impl Node for IndexExpression {
 	fn token_literal(&self) -> String {
@@ -123,18 +148,12 @@ impl Node for IndexExpression {
 	fn string(&self) -> String {
 		format!("{}[{}]", self.left.string(), self.index.string())
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ArrayLiteral {
 	pub tok: Token,
-	pub mems: Vec<Box<dyn Node>>,
+	pub mems: Vec<Expr>,
 }
 impl Node for ArrayLiteral {
 	fn token_literal(&self) -> String {
@@ -145,14 +164,8 @@ impl Node for ArrayLiteral {
 			.iter()
 			.fold(String::new(), |acc, e| acc + e.string().as_str())
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct StringLiteral {
 	pub token: Token,
 	pub val: String,
@@ -165,21 +178,13 @@ impl Node for StringLiteral {
 	fn string(&self) -> String {
 		self.token.literal.clone()
 	}
-
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CallExpression {
 	pub token: Token,
-	pub function: BoxNode,
-	pub args: Vec<BoxNode>,
+	pub function: Box<Expr>,
+	pub args: Vec<Expr>,
 }
 impl Node for CallExpression {
 	fn token_literal(&self) -> String {
@@ -192,23 +197,17 @@ impl Node for CallExpression {
 			.iter()
 			.fold("".to_string(), |acc, a| acc + a.string().as_str() + " ,");
 		let mut out = self.function.string();
-		out.push_str("(");
+		out.push('(');
 		out.push_str(args.as_str());
-		out.push_str(")");
+		out.push(')');
 
 		out
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct IfExpression {
 	pub token: Token,
-	pub condition: BoxNode,
+	pub condition: Box<Expr>,
 	pub consequence: BlockStatement,
 	pub alternative: Option<BlockStatement>,
 }
@@ -218,24 +217,23 @@ impl Node for IfExpression {
 	}
 
 	fn string(&self) -> String {
-		let out = "if ".to_string()
-			+ &self.condition.string()
-			+ " " + &self.consequence.string();
+		let out = format!(
+			"if ({}) {{{}\n}}",
+			self.condition.string(),
+			self.consequence.string()
+		);
 
 		match &self.alternative {
-			Some(bs) => return out + bs.string().as_str(),
+			Some(bs) if bs.token.token_type == TokenType::If => {
+				out + " else " + bs.string().trim_start()
+			}
+			Some(bs) => out + " else {" + bs.string().as_str() + "\n}",
 
-			None => return out,
+			None => out,
 		}
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct FunctionLiteral {
 	pub token: Token,
 	pub params: Vec<Identifier>,
@@ -249,30 +247,24 @@ impl Node for FunctionLiteral {
 	fn string(&self) -> String {
 		let params = self.params.iter().fold("".to_string(), |mut acc, p| {
 			acc.push_str(p.string().as_str());
-			acc.push_str(",");
+			acc.push(',');
 			acc
 		});
 
 		let mut out = self.token_literal();
-		out.push_str("(");
+		out.push('(');
 		out.push_str(params.as_str());
-		out.push_str(")");
+		out.push(')');
 		out.push_str(self.body.string().as_str());
 
 		out
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct BlockStatement {
 	pub token: Token,
-	pub statements: Vec<ResultNode>,
+	pub statements: Vec<Stmt>,
 }
 impl Node for BlockStatement {
 	fn token_literal(&self) -> String {
@@ -282,40 +274,27 @@ impl Node for BlockStatement {
 	fn string(&self) -> String {
 		self.statements
 			.iter()
-			.clone()
 			.fold("".to_string(), |acc, x| acc + "\n" + &x.string())
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct BooleanLiteral {
 	pub token: Token,
 	pub value: bool,
 }
 impl Node for BooleanLiteral {
 	fn token_literal(&self) -> String {
-		self.value.to_string()
+		self.token.literal.clone()
 	}
 
 	fn string(&self) -> String {
 		self.value.to_string()
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ExpressionStatement {
 	pub token: Token,
-	pub expression: BoxNode,
+	pub expression: Expr,
 }
 impl Node for ExpressionStatement {
 	fn token_literal(&self) -> String {
@@ -325,18 +304,12 @@ impl Node for ExpressionStatement {
 	fn string(&self) -> String {
 		self.expression.string()
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct LetStatement {
 	pub token: Token,
 	pub name: Identifier,
-	pub value: BoxNode,
+	pub value: Expr,
 }
 
 impl Node for LetStatement {
@@ -350,17 +323,11 @@ impl Node for LetStatement {
 			+ " = " + &self.value.string()
 			+ ";"
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ReturnStatement {
 	pub token: Token,
-	pub value: BoxNode,
+	pub value: Expr,
 }
 impl Node for ReturnStatement {
 	fn token_literal(&self) -> String {
@@ -370,15 +337,79 @@ impl Node for ReturnStatement {
 	fn string(&self) -> String {
 		self.token_literal() + " " + &self.value.string() + ";"
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WhileStatement {
+	pub token: Token,
+	pub condition: Expr,
+	pub body: BlockStatement,
+}
+impl Node for WhileStatement {
+	fn token_literal(&self) -> String {
+		self.token.literal.clone()
 	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
+
+	fn string(&self) -> String {
+		"while ".to_string() + &self.condition.string() + " " + &self.body.string()
 	}
 }
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForStatement {
+	pub token: Token,
+	pub vars: Vec<Identifier>,
+	pub iterable: Expr,
+	pub body: BlockStatement,
+}
+impl Node for ForStatement {
+	fn token_literal(&self) -> String {
+		self.token.literal.clone()
+	}
 
-#[derive(Clone)]
+	fn string(&self) -> String {
+		let vars = self
+			.vars
+			.iter()
+			.map(|v| v.string())
+			.collect::<Vec<String>>()
+			.join(", ");
+		format!(
+			"for ({} in {}) {}",
+			vars,
+			self.iterable.string(),
+			self.body.string()
+		)
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakStatement {
+	pub token: Token,
+}
+impl Node for BreakStatement {
+	fn token_literal(&self) -> String {
+		self.token.literal.clone()
+	}
+
+	fn string(&self) -> String {
+		self.token_literal() + ";"
+	}
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContinueStatement {
+	pub token: Token,
+}
+impl Node for ContinueStatement {
+	fn token_literal(&self) -> String {
+		self.token.literal.clone()
+	}
+
+	fn string(&self) -> String {
+		self.token_literal() + ";"
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Identifier {
 	pub token: Token,
 	pub value: String,
@@ -391,14 +422,8 @@ impl Node for Identifier {
 	fn string(&self) -> String {
 		self.value.clone()
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
-	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
-	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct IntegerLiteral {
 	pub token: Token,
 	pub value: i64,
@@ -412,18 +437,27 @@ impl Node for IntegerLiteral {
 	fn string(&self) -> String {
 		self.token.literal.clone()
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct FloatLiteral {
+	pub token: Token,
+	pub value: f64,
+}
+
+impl Node for FloatLiteral {
+	fn token_literal(&self) -> String {
+		self.token.literal.clone()
 	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
+
+	fn string(&self) -> String {
+		self.token.literal.clone()
 	}
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PrefixExpression {
 	pub token: Token,
 	pub operator: String,
-	pub right: BoxNode,
+	pub right: Box<Expr>,
 }
 
 impl Node for PrefixExpression {
@@ -434,19 +468,61 @@ impl Node for PrefixExpression {
 	fn string(&self) -> String {
 		"(".to_owned() + &self.operator + &self.right.string() + ")"
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssignExpression {
+	pub token: Token,
+	pub name: Identifier,
+	pub operator: String,
+	pub value: Box<Expr>,
+}
+
+impl Node for AssignExpression {
+	fn token_literal(&self) -> String {
+		self.token.literal.clone()
+	}
+
+	fn string(&self) -> String {
+		format!(
+			"({} {} {})",
+			self.name.string(),
+			self.operator,
+			self.value.string()
+		)
 	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexAssignExpression {
+	pub token: Token,
+	pub left: Box<Expr>,
+	pub index: Box<Expr>,
+	pub operator: String,
+	pub value: Box<Expr>,
+}
+
+impl Node for IndexAssignExpression {
+	fn token_literal(&self) -> String {
+		self.token.literal.clone()
+	}
+
+	fn string(&self) -> String {
+		format!(
+			"({}[{}] {} {})",
+			self.left.string(),
+			self.index.string(),
+			self.operator,
+			self.value.string()
+		)
 	}
 }
-#[derive(Clone)]
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct InfixExpression {
 	pub token: Token,
-	pub left: BoxNode,
+	pub left: Box<Expr>,
 	pub operator: String,
-	pub right: BoxNode,
+	pub right: Box<Expr>,
 }
 
 impl Node for InfixExpression {
@@ -457,10 +533,23 @@ impl Node for InfixExpression {
 	fn string(&self) -> String {
 		"(".to_owned() + &self.left.string() + &self.operator + &self.right.string() + ")"
 	}
-	fn into_eval_node(self: Box<Self>) -> Box<dyn EvalNode> {
-		Box::new(Eval { node: *self })
+}
+
+// && and ||, the right side is only evaluated when the left does not decide
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogicalExpression {
+	pub token: Token,
+	pub left: Box<Expr>,
+	pub operator: String,
+	pub right: Box<Expr>,
+}
+
+impl Node for LogicalExpression {
+	fn token_literal(&self) -> String {
+		self.token.literal.clone()
 	}
-	fn clone_into_node(&self) -> Box<dyn Node> {
-		Box::new(self.clone())
+
+	fn string(&self) -> String {
+		"(".to_owned() + &self.left.string() + &self.operator + &self.right.string() + ")"
 	}
 }
@@ -0,0 +1,202 @@
+use super::*;
+
+// read-only walk over the tree, override a method to look at a node and call
+// the matching walk_ function from it to keep going into the children
+pub trait Visitor {
+	fn visit_program(&mut self, n: &Program) {
+		walk_program(self, n)
+	}
+	fn visit_block(&mut self, n: &BlockStatement) {
+		walk_block(self, n)
+	}
+	fn visit_stmt(&mut self, n: &Stmt) {
+		walk_stmt(self, n)
+	}
+	fn visit_expr(&mut self, n: &Expr) {
+		walk_expr(self, n)
+	}
+
+	fn visit_let(&mut self, n: &LetStatement) {
+		walk_let(self, n)
+	}
+	fn visit_return(&mut self, n: &ReturnStatement) {
+		walk_return(self, n)
+	}
+	fn visit_expression_stmt(&mut self, n: &ExpressionStatement) {
+		walk_expression_stmt(self, n)
+	}
+	fn visit_while(&mut self, n: &WhileStatement) {
+		walk_while(self, n)
+	}
+	fn visit_for(&mut self, n: &ForStatement) {
+		walk_for(self, n)
+	}
+	fn visit_break(&mut self, _n: &BreakStatement) {}
+	fn visit_continue(&mut self, _n: &ContinueStatement) {}
+
+	// names that are declared or assigned: let, assignment, parameters and loop variables
+	fn visit_identifier(&mut self, _n: &Identifier) {}
+	// a name that is read
+	fn visit_identifier_expr(&mut self, _n: &Identifier) {}
+	fn visit_integer(&mut self, _n: &IntegerLiteral) {}
+	fn visit_float(&mut self, _n: &FloatLiteral) {}
+	fn visit_string(&mut self, _n: &StringLiteral) {}
+	fn visit_boolean(&mut self, _n: &BooleanLiteral) {}
+	fn visit_array(&mut self, n: &ArrayLiteral) {
+		walk_array(self, n)
+	}
+	fn visit_hash(&mut self, n: &HashLiteral) {
+		walk_hash(self, n)
+	}
+	fn visit_index(&mut self, n: &IndexExpression) {
+		walk_index(self, n)
+	}
+	fn visit_prefix(&mut self, n: &PrefixExpression) {
+		walk_prefix(self, n)
+	}
+	fn visit_infix(&mut self, n: &InfixExpression) {
+		walk_infix(self, n)
+	}
+	fn visit_logical(&mut self, n: &LogicalExpression) {
+		walk_logical(self, n)
+	}
+	fn visit_assign(&mut self, n: &AssignExpression) {
+		walk_assign(self, n)
+	}
+	fn visit_index_assign(&mut self, n: &IndexAssignExpression) {
+		walk_index_assign(self, n)
+	}
+	fn visit_if(&mut self, n: &IfExpression) {
+		walk_if(self, n)
+	}
+	fn visit_function(&mut self, n: &FunctionLiteral) {
+		walk_function(self, n)
+	}
+	fn visit_call(&mut self, n: &CallExpression) {
+		walk_call(self, n)
+	}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(v: &mut V, n: &Program) {
+	n.statements.iter().for_each(|s| v.visit_stmt(s));
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(v: &mut V, n: &BlockStatement) {
+	n.statements.iter().for_each(|s| v.visit_stmt(s));
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(v: &mut V, n: &Stmt) {
+	match n {
+		Stmt::Let(n) => v.visit_let(n),
+		Stmt::Return(n) => v.visit_return(n),
+		Stmt::Expression(n) => v.visit_expression_stmt(n),
+		Stmt::While(n) => v.visit_while(n),
+		Stmt::For(n) => v.visit_for(n),
+		Stmt::Break(n) => v.visit_break(n),
+		Stmt::Continue(n) => v.visit_continue(n),
+	}
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(v: &mut V, n: &Expr) {
+	match n {
+		Expr::Identifier(n) => v.visit_identifier_expr(n),
+		Expr::Integer(n) => v.visit_integer(n),
+		Expr::Float(n) => v.visit_float(n),
+		Expr::String(n) => v.visit_string(n),
+		Expr::Boolean(n) => v.visit_boolean(n),
+		Expr::Array(n) => v.visit_array(n),
+		Expr::Hash(n) => v.visit_hash(n),
+		Expr::Index(n) => v.visit_index(n),
+		Expr::Prefix(n) => v.visit_prefix(n),
+		Expr::Infix(n) => v.visit_infix(n),
+		Expr::Logical(n) => v.visit_logical(n),
+		Expr::Assign(n) => v.visit_assign(n),
+		Expr::IndexAssign(n) => v.visit_index_assign(n),
+		Expr::If(n) => v.visit_if(n),
+		Expr::Function(n) => v.visit_function(n),
+		Expr::Call(n) => v.visit_call(n),
+	}
+}
+
+pub fn walk_let<V: Visitor + ?Sized>(v: &mut V, n: &LetStatement) {
+	v.visit_identifier(&n.name);
+	v.visit_expr(&n.value);
+}
+
+pub fn walk_return<V: Visitor + ?Sized>(v: &mut V, n: &ReturnStatement) {
+	v.visit_expr(&n.value);
+}
+
+pub fn walk_expression_stmt<V: Visitor + ?Sized>(v: &mut V, n: &ExpressionStatement) {
+	v.visit_expr(&n.expression);
+}
+
+pub fn walk_while<V: Visitor + ?Sized>(v: &mut V, n: &WhileStatement) {
+	v.visit_expr(&n.condition);
+	v.visit_block(&n.body);
+}
+
+pub fn walk_for<V: Visitor + ?Sized>(v: &mut V, n: &ForStatement) {
+	n.vars.iter().for_each(|i| v.visit_identifier(i));
+	v.visit_expr(&n.iterable);
+	v.visit_block(&n.body);
+}
+
+pub fn walk_array<V: Visitor + ?Sized>(v: &mut V, n: &ArrayLiteral) {
+	n.mems.iter().for_each(|m| v.visit_expr(m));
+}
+
+pub fn walk_hash<V: Visitor + ?Sized>(v: &mut V, n: &HashLiteral) {
+	n.pairs.iter().for_each(|(k, val)| {
+		v.visit_expr(k);
+		v.visit_expr(val);
+	});
+}
+
+pub fn walk_index<V: Visitor + ?Sized>(v: &mut V, n: &IndexExpression) {
+	v.visit_expr(&n.left);
+	v.visit_expr(&n.index);
+}
+
+pub fn walk_prefix<V: Visitor + ?Sized>(v: &mut V, n: &PrefixExpression) {
+	v.visit_expr(&n.right);
+}
+
+pub fn walk_infix<V: Visitor + ?Sized>(v: &mut V, n: &InfixExpression) {
+	v.visit_expr(&n.left);
+	v.visit_expr(&n.right);
+}
+
+pub fn walk_logical<V: Visitor + ?Sized>(v: &mut V, n: &LogicalExpression) {
+	v.visit_expr(&n.left);
+	v.visit_expr(&n.right);
+}
+
+pub fn walk_assign<V: Visitor + ?Sized>(v: &mut V, n: &AssignExpression) {
+	v.visit_identifier(&n.name);
+	v.visit_expr(&n.value);
+}
+
+pub fn walk_index_assign<V: Visitor + ?Sized>(v: &mut V, n: &IndexAssignExpression) {
+	v.visit_expr(&n.left);
+	v.visit_expr(&n.index);
+	v.visit_expr(&n.value);
+}
+
+pub fn walk_if<V: Visitor + ?Sized>(v: &mut V, n: &IfExpression) {
+	v.visit_expr(&n.condition);
+	v.visit_block(&n.consequence);
+	if let Some(alternative) = &n.alternative {
+		v.visit_block(alternative);
+	}
+}
+
+pub fn walk_function<V: Visitor + ?Sized>(v: &mut V, n: &FunctionLiteral) {
+	n.params.iter().for_each(|p| v.visit_identifier(p));
+	v.visit_block(&n.body);
+}
+
+pub fn walk_call<V: Visitor + ?Sized>(v: &mut V, n: &CallExpression) {
+	v.visit_expr(&n.function);
+	n.args.iter().for_each(|a| v.visit_expr(a));
+}
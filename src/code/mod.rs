@@ -0,0 +1,152 @@
+pub struct Definition {
+	pub name: &'static str,
+	pub operand_widths: &'static [usize],
+}
+
+macro_rules! opcodes {
+	($($op:ident => [$($width:expr),*]),* $(,)?) => {
+		#[repr(u8)]
+		#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+		pub enum Opcode {
+			$($op),*
+		}
+
+		const OPCODES: &[Opcode] = &[$(Opcode::$op),*];
+
+		impl Opcode {
+			pub fn definition(&self) -> Definition {
+				match self {
+					$(Opcode::$op => Definition {
+						name: concat!("Op", stringify!($op)),
+						operand_widths: &[$($width),*],
+					}),*
+				}
+			}
+		}
+	};
+}
+
+opcodes! {
+	Constant => [2],
+	Pop => [],
+	Add => [],
+	Sub => [],
+	Mul => [],
+	Div => [],
+	True => [],
+	False => [],
+	Null => [],
+	Equal => [],
+	NotEqual => [],
+	GreaterThan => [],
+	LessThan => [],
+	Minus => [],
+	Bang => [],
+	JumpNotTruthy => [2],
+	Jump => [2],
+	GetGlobal => [2],
+	SetGlobal => [2],
+	GetLocal => [1],
+	SetLocal => [1],
+	GetBuiltin => [1],
+	Array => [2],
+	Hash => [2],
+	Index => [],
+	Call => [1],
+	ReturnValue => [],
+	Return => [],
+	Closure => [2, 1],
+	GetFree => [1],
+	Mod => [],
+	SetFree => [1],
+	SetIndex => [1],
+	Iter => [],
+	IterNext => [2, 1],
+	FreshLocal => [1],
+	JumpNotTruthyOrPop => [2],
+	JumpTruthyOrPop => [2],
+	GreaterEqual => [],
+	LessEqual => [],
+	EnterLoop => [],
+	LeaveLoop => [],
+	LoopJump => [2],
+}
+
+// the operand of SetIndex is a position in this list
+pub const ASSIGN_OPERATORS: &[&str] = &["=", "+=", "-=", "*=", "/=", "%="];
+
+impl Opcode {
+	pub fn from_byte(byte: u8) -> Option<Opcode> {
+		OPCODES.get(byte as usize).copied()
+	}
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Instructions(pub Vec<u8>);
+
+// (instruction offset, source line) pairs, one entry each time the line changes
+pub type LineTable = Vec<(usize, usize)>;
+
+impl Instructions {
+	pub fn string(&self) -> String {
+		let mut out = String::new();
+		let mut i = 0;
+		while i < self.0.len() {
+			let op = match Opcode::from_byte(self.0[i]) {
+				Some(op) => op,
+				None => {
+					out += format!("{:04} ERROR: unknown opcode {}\n", i, self.0[i]).as_str();
+					i += 1;
+					continue;
+				}
+			};
+			let def = op.definition();
+			let (operands, read) = read_operands(&def, &self.0[i + 1..]);
+			let operands = operands
+				.iter()
+				.fold(String::new(), |acc, o| acc + " " + o.to_string().as_str());
+			out += format!("{:04} {}{}\n", i, def.name, operands).as_str();
+			i += 1 + read;
+		}
+		out
+	}
+}
+
+pub fn make(op: Opcode, operands: &[usize]) -> Vec<u8> {
+	let def = op.definition();
+	let mut ins = vec![op as u8];
+	for (o, w) in operands.iter().zip(def.operand_widths) {
+		match w {
+			2 => ins.extend_from_slice(&(*o as u16).to_be_bytes()),
+			1 => ins.push(*o as u8),
+			_ => unreachable!("operand widths are either 1 or 2 bytes"),
+		}
+	}
+	ins
+}
+
+pub fn read_operands(def: &Definition, ins: &[u8]) -> (Vec<usize>, usize) {
+	let mut offset = 0;
+	let operands = def
+		.operand_widths
+		.iter()
+		.map(|w| {
+			let operand = match w {
+				2 => read_u16(&ins[offset..]) as usize,
+				1 => read_u8(&ins[offset..]) as usize,
+				_ => unreachable!("operand widths are either 1 or 2 bytes"),
+			};
+			offset += w;
+			operand
+		})
+		.collect();
+	(operands, offset)
+}
+
+pub fn read_u16(ins: &[u8]) -> u16 {
+	u16::from_be_bytes([ins[0], ins[1]])
+}
+
+pub fn read_u8(ins: &[u8]) -> u8 {
+	ins[0]
+}
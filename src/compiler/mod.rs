@@ -0,0 +1,812 @@
+pub mod symbol_table;
+
+use crate::ast::*;
+use crate::code::{make, Instructions, LineTable, Opcode, ASSIGN_OPERATORS};
+use crate::object::*;
+use crate::parser::ParserError;
+use crate::token::Span;
+use std::mem;
+use std::rc::Rc;
+use symbol_table::{Symbol, SymbolScope, SymbolTable};
+
+type ResultCompile = Result<(), CompileError>;
+
+#[derive(Debug)]
+pub enum CompileError {
+	ParserError(ParserError),
+	Undefined(String, Span),
+	InvalidAssignment(String, Span),
+	Unsupported(String),
+	LimitExceeded(String),
+}
+
+impl CompileError {
+	pub fn get_err_type(&self) -> String {
+		match self {
+			CompileError::ParserError(_) => String::from("ParserError"),
+			CompileError::Undefined(..) => String::from("Undefined"),
+			CompileError::InvalidAssignment(..) => String::from("InvalidAssignment"),
+			CompileError::Unsupported(_) => String::from("Unsupported"),
+			CompileError::LimitExceeded(_) => String::from("LimitExceeded"),
+		}
+	}
+	pub fn get_err_msg(&self) -> String {
+		match self {
+			CompileError::ParserError(e) => e.get_err_msg(),
+			CompileError::Undefined(m, _) => m.to_string(),
+			CompileError::InvalidAssignment(m, _) => m.to_string(),
+			CompileError::Unsupported(m) => m.to_string(),
+			CompileError::LimitExceeded(m) => m.to_string(),
+		}
+	}
+	pub fn get_span(&self) -> Option<Span> {
+		match self {
+			CompileError::ParserError(e) => Some(e.get_span()),
+			CompileError::Undefined(_, s) => Some(*s),
+			CompileError::InvalidAssignment(_, s) => Some(*s),
+			CompileError::Unsupported(_) => None,
+			CompileError::LimitExceeded(_) => None,
+		}
+	}
+}
+
+pub struct Bytecode {
+	pub instructions: Instructions,
+	pub lines: LineTable,
+	pub constants: Vec<Value>,
+	pub num_locals: usize,
+}
+
+#[derive(Default)]
+struct CompilationScope {
+	instructions: Instructions,
+	lines: LineTable,
+	loops: Vec<Loop>,
+}
+
+// the jumps of each break and continue, patched once the loop has been compiled
+#[derive(Default)]
+struct Loop {
+	breaks: Vec<usize>,
+	continues: Vec<usize>,
+}
+
+pub struct Compiler {
+	constants: Vec<Value>,
+	symbol_table: SymbolTable,
+	scopes: Vec<CompilationScope>,
+	errors: Vec<CompileError>,
+}
+
+impl Default for Compiler {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Compiler {
+	pub fn new() -> Self {
+		Compiler {
+			constants: Vec::new(),
+			symbol_table: SymbolTable::new(),
+			scopes: vec![CompilationScope::default()],
+			errors: Vec::new(),
+		}
+	}
+
+	// syntax errors and undefined identifiers do not stop compilation so that all of them get reported
+	pub fn compile(&mut self, program: Program) -> Result<(), Vec<CompileError>> {
+		compile_node(program, self).map_err(|e| vec![e])?;
+		match self.errors.is_empty() {
+			true => Ok(()),
+			false => Err(mem::take(&mut self.errors)),
+		}
+	}
+
+	pub fn bytecode(mut self) -> Bytecode {
+		let num_locals = self.symbol_table.num_block_locals;
+		let scope = self.leave_scope();
+		Bytecode {
+			instructions: scope.instructions,
+			lines: scope.lines,
+			constants: self.constants,
+			num_locals,
+		}
+	}
+
+	fn current_scope(&mut self) -> &mut CompilationScope {
+		self.scopes.last_mut().expect("compiler always has a scope")
+	}
+
+	fn current_loop(&mut self) -> Result<&mut Loop, CompileError> {
+		self.current_scope().loops.last_mut().ok_or_else(|| {
+			CompileError::Unsupported(String::from("break or continue outside of a loop"))
+		})
+	}
+
+	fn patch_loop(
+		&mut self,
+		breaks: Vec<usize>,
+		end_pos: usize,
+		continues: Vec<usize>,
+		continue_pos: usize,
+	) -> ResultCompile {
+		breaks
+			.into_iter()
+			.try_for_each(|b| self.change_operand(b, end_pos))?;
+		continues
+			.into_iter()
+			.try_for_each(|c| self.change_operand(c, continue_pos))
+	}
+
+	fn current_position(&mut self) -> usize {
+		self.current_scope().instructions.0.len()
+	}
+
+	fn emit(&mut self, op: Opcode, operands: &[usize]) -> Result<usize, CompileError> {
+		check_operands(op, operands)?;
+		let ins = make(op, operands);
+		let pos = self.current_position();
+		self.current_scope().instructions.0.extend(ins);
+		Ok(pos)
+	}
+
+	fn mark_line(&mut self, line: usize) {
+		let pos = self.current_position();
+		let lines = &mut self.current_scope().lines;
+		match lines.last_mut() {
+			Some((_, l)) if *l == line => (),
+			Some((p, l)) if *p == pos => *l = line,
+			_ => lines.push((pos, line)),
+		}
+	}
+
+	fn change_operand(&mut self, pos: usize, operand: usize) -> ResultCompile {
+		let instructions = &mut self.current_scope().instructions.0;
+		let op = Opcode::from_byte(instructions[pos]).expect("patching a known opcode");
+		check_operands(op, &[operand])?;
+		let ins = make(op, &[operand]);
+		instructions[pos..pos + ins.len()].copy_from_slice(&ins);
+		Ok(())
+	}
+
+	fn add_constant(&mut self, obj: Value) -> usize {
+		self.constants.push(obj);
+		self.constants.len() - 1
+	}
+
+	fn enter_scope(&mut self) {
+		self.scopes.push(CompilationScope::default());
+		let outer = mem::take(&mut self.symbol_table);
+		self.symbol_table = SymbolTable::new_enclosed(outer);
+	}
+
+	fn leave_scope(&mut self) -> CompilationScope {
+		if let Some(outer) = self.symbol_table.outer.take() {
+			self.symbol_table = *outer;
+		}
+		self.scopes.pop().expect("compiler always has a scope")
+	}
+
+	fn load_symbol(&mut self, symbol: &Symbol) -> ResultCompile {
+		match symbol.scope {
+			SymbolScope::Global => self.emit(Opcode::GetGlobal, &[symbol.index])?,
+			SymbolScope::Local => self.emit(Opcode::GetLocal, &[symbol.index])?,
+			SymbolScope::Builtin => self.emit(Opcode::GetBuiltin, &[symbol.index])?,
+			SymbolScope::Free => self.emit(Opcode::GetFree, &[symbol.index])?,
+		};
+		Ok(())
+	}
+
+	fn store_symbol(&mut self, symbol: &Symbol) -> ResultCompile {
+		match symbol.scope {
+			SymbolScope::Global => self.emit(Opcode::SetGlobal, &[symbol.index])?,
+			SymbolScope::Local => self.emit(Opcode::SetLocal, &[symbol.index])?,
+			SymbolScope::Free => self.emit(Opcode::SetFree, &[symbol.index])?,
+			SymbolScope::Builtin => unreachable!("builtins cannot be assigned"),
+		};
+		Ok(())
+	}
+
+	fn compile_statements(&mut self, statements: Vec<Stmt>) -> ResultCompile {
+		statements
+			.into_iter()
+			.try_for_each(|s| compile_node(s, self))
+	}
+
+	// the names declared by the statements of a scope are bound before any of them is
+	// compiled so a function can use a binding made after it, unless that would hide
+	// a binding that is already visible
+	fn hoist_lets(&mut self, statements: &[Stmt]) {
+		statements.iter().for_each(|s| match s {
+			Stmt::Let(l) if !self.symbol_table.is_visible(&l.name.value) => {
+				self.symbol_table.define(l.name.value.clone());
+			}
+			_ => (),
+		});
+	}
+
+	// leaves the value of the block on the stack, the last statement when it is an
+	// expression and null otherwise
+	fn compile_block_value(&mut self, block: BlockStatement) -> ResultCompile {
+		let ends_in_expression = matches!(block.statements.last(), Some(Stmt::Expression(_)));
+		compile_node(block, self)?;
+		match ends_in_expression {
+			true => self.remove_last_pop(),
+			false => self.emit(Opcode::Null, &[]).map(|_| ()),
+		}
+	}
+
+	fn remove_last_pop(&mut self) -> ResultCompile {
+		let instructions = &mut self.current_scope().instructions.0;
+		match instructions.last().copied().and_then(Opcode::from_byte) {
+			Some(Opcode::Pop) => {
+				instructions.pop();
+				Ok(())
+			}
+			_ => Err(CompileError::Unsupported(String::from(
+				"block value without a trailing pop",
+			))),
+		}
+	}
+}
+
+fn check_operands(op: Opcode, operands: &[usize]) -> ResultCompile {
+	let def = op.definition();
+	def.operand_widths
+		.iter()
+		.zip(operands)
+		.try_for_each(|(w, o)| {
+			if o >> (8 * w) != 0 {
+				return Err(CompileError::LimitExceeded(format!(
+					"{} operand {} does not fit in {} bytes",
+					def.name, o, w
+				)));
+			}
+			Ok(())
+		})
+}
+
+pub trait CompileNode {
+	fn compile(self, c: &mut Compiler) -> ResultCompile;
+}
+
+pub struct Compile<N: Node> {
+	pub node: N,
+}
+
+pub fn compile_node<N: Node>(node: N, c: &mut Compiler) -> ResultCompile
+where
+	Compile<N>: CompileNode,
+{
+	Compile { node }.compile(c)
+}
+
+impl CompileNode for Compile<Stmt> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		match self.node {
+			Stmt::Let(n) => compile_node(n, c),
+			Stmt::Return(n) => compile_node(n, c),
+			Stmt::Expression(n) => compile_node(n, c),
+			Stmt::While(n) => compile_node(n, c),
+			Stmt::For(n) => compile_node(n, c),
+			Stmt::Break(n) => compile_node(n, c),
+			Stmt::Continue(n) => compile_node(n, c),
+		}
+	}
+}
+
+impl CompileNode for Compile<Expr> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		match self.node {
+			Expr::Identifier(n) => compile_node(n, c),
+			Expr::Integer(n) => compile_node(n, c),
+			Expr::Float(n) => compile_node(n, c),
+			Expr::String(n) => compile_node(n, c),
+			Expr::Boolean(n) => compile_node(n, c),
+			Expr::Array(n) => compile_node(n, c),
+			Expr::Hash(n) => compile_node(n, c),
+			Expr::Index(n) => compile_node(n, c),
+			Expr::Prefix(n) => compile_node(n, c),
+			Expr::Infix(n) => compile_node(n, c),
+			Expr::Logical(n) => compile_node(n, c),
+			Expr::Assign(n) => compile_node(n, c),
+			Expr::IndexAssign(n) => compile_node(n, c),
+			Expr::If(n) => compile_node(n, c),
+			Expr::Function(n) => compile_node(n, c),
+			Expr::Call(n) => compile_node(n, c),
+		}
+	}
+}
+
+impl CompileNode for Compile<Program> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let Program { statements, errors } = self.node;
+		if !errors.is_empty() {
+			c.errors
+				.extend(errors.into_iter().map(CompileError::ParserError));
+			return Ok(());
+		}
+		c.hoist_lets(&statements);
+		c.compile_statements(statements)
+	}
+}
+
+impl CompileNode for Compile<BlockStatement> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		c.compile_statements(self.node.statements)
+	}
+}
+
+impl CompileNode for Compile<ExpressionStatement> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		c.mark_line(self.node.token.span.line);
+		compile_node(self.node.expression, c)?;
+		c.emit(Opcode::Pop, &[])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<LetStatement> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let LetStatement { token, name, value } = self.node;
+		c.mark_line(token.span.line);
+		// a function is bound before its body is compiled so it can call itself
+		let is_function = matches!(value, Expr::Function(_));
+		let symbol = match is_function {
+			true => {
+				let symbol = c.symbol_table.define(name.value);
+				compile_node(value, c)?;
+				symbol
+			}
+			false => {
+				compile_node(value, c)?;
+				c.symbol_table.define(name.value)
+			}
+		};
+		c.store_symbol(&symbol)
+	}
+}
+
+impl CompileNode for Compile<AssignExpression> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let AssignExpression {
+			token,
+			name,
+			operator,
+			value,
+		} = self.node;
+		let symbol = match c.symbol_table.resolve(&name.value) {
+			Some(s) if s.scope == SymbolScope::Builtin => {
+				c.errors.push(CompileError::InvalidAssignment(
+					format!("Cannot assign to builtin {}", name.value),
+					name.token.span,
+				));
+				None
+			}
+			Some(s) => Some(s),
+			None => {
+				c.errors.push(CompileError::Undefined(
+					format!("Cannot assign to {}, it was never declared", name.value),
+					name.token.span,
+				));
+				None
+			}
+		};
+
+		// on an error the value is still left on the stack so compilation can carry on
+		match operator.strip_suffix('=').filter(|op| !op.is_empty()) {
+			Some(op) => {
+				match &symbol {
+					Some(s) => c.load_symbol(s)?,
+					None => {
+						c.emit(Opcode::Null, &[])?;
+					}
+				};
+				compile_node(*value, c)?;
+				let op = infix_opcode(op).ok_or_else(|| {
+					CompileError::Unsupported(format!(
+						"Unknown assignment operator {}",
+						token.literal
+					))
+				})?;
+				c.emit(op, &[])?;
+			}
+			None => compile_node(*value, c)?,
+		};
+		if let Some(s) = symbol {
+			c.store_symbol(&s)?;
+			c.load_symbol(&s)?;
+		}
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<IndexAssignExpression> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let IndexAssignExpression {
+			token: _,
+			left,
+			index,
+			operator,
+			value,
+		} = self.node;
+		compile_node(*left, c)?;
+		compile_node(*index, c)?;
+		compile_node(*value, c)?;
+		let op = ASSIGN_OPERATORS
+			.iter()
+			.position(|o| *o == operator)
+			.ok_or_else(|| {
+				CompileError::Unsupported(format!("Unknown assignment operator {}", operator))
+			})?;
+		c.emit(Opcode::SetIndex, &[op])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<ReturnStatement> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		c.mark_line(self.node.token.span.line);
+		compile_node(self.node.value, c)?;
+		c.emit(Opcode::ReturnValue, &[])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<WhileStatement> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let WhileStatement {
+			token,
+			condition,
+			body,
+		} = self.node;
+		c.mark_line(token.span.line);
+		c.emit(Opcode::EnterLoop, &[])?;
+		let start = c.current_position();
+		compile_node(condition, c)?;
+		let jump_not_truthy = c.emit(Opcode::JumpNotTruthy, &[0])?;
+
+		c.current_scope().loops.push(Loop::default());
+		let body = compile_node(body, c);
+		let Loop { breaks, continues } = c.current_scope().loops.pop().expect("pushed above");
+		body?;
+		c.emit(Opcode::Jump, &[start])?;
+
+		let end_pos = c.current_position();
+		c.emit(Opcode::LeaveLoop, &[])?;
+		c.change_operand(jump_not_truthy, end_pos)?;
+		c.patch_loop(breaks, end_pos, continues, start)
+	}
+}
+
+impl CompileNode for Compile<ForStatement> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let ForStatement {
+			token,
+			vars,
+			iterable,
+			body,
+		} = self.node;
+		c.mark_line(token.span.line);
+		compile_node(iterable, c)?;
+		c.emit(Opcode::Iter, &[])?;
+		c.emit(Opcode::EnterLoop, &[])?;
+		let next = c.emit(Opcode::IterNext, &[0, vars.len()])?;
+		let jump_fresh = c.emit(Opcode::Jump, &[0])?;
+
+		let body_pos = c.current_position();
+		c.symbol_table.enter_block();
+		c.current_scope().loops.push(Loop::default());
+		// the values were pushed in order so the last variable is on top
+		let symbols = vars
+			.into_iter()
+			.map(|v| c.symbol_table.define(v.value))
+			.collect::<Vec<Symbol>>();
+		c.hoist_lets(&body.statements);
+		let body = symbols
+			.iter()
+			.rev()
+			.try_for_each(|s| c.store_symbol(s))
+			.and_then(|_| compile_node(body, c));
+		let Loop { breaks, continues } = c.current_scope().loops.pop().expect("pushed above");
+		let block = c.symbol_table.leave_block();
+		body?;
+		c.emit(Opcode::Jump, &[next])?;
+
+		// every variable of the body gets a new slot each time round, so a closure
+		// made in one iteration keeps that iteration's values
+		let fresh_pos = c.current_position();
+		c.change_operand(jump_fresh, fresh_pos)?;
+		block
+			.iter()
+			.try_for_each(|s| c.emit(Opcode::FreshLocal, &[s.index]).map(|_| ()))?;
+		c.emit(Opcode::Jump, &[body_pos])?;
+
+		let end_pos = c.current_position();
+		c.emit(Opcode::LeaveLoop, &[])?;
+		c.change_operand(next, end_pos)?;
+		c.patch_loop(breaks, end_pos, continues, next)?;
+		c.emit(Opcode::Pop, &[])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<BreakStatement> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		c.mark_line(self.node.token.span.line);
+		let jump = c.emit(Opcode::LoopJump, &[0])?;
+		c.current_loop()?.breaks.push(jump);
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<ContinueStatement> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		c.mark_line(self.node.token.span.line);
+		let jump = c.emit(Opcode::LoopJump, &[0])?;
+		c.current_loop()?.continues.push(jump);
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<IntegerLiteral> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let i = c.add_constant(Value::Integer(self.node.value));
+		c.emit(Opcode::Constant, &[i])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<FloatLiteral> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let i = c.add_constant(Value::Float(self.node.value));
+		c.emit(Opcode::Constant, &[i])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<StringLiteral> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let i = c.add_constant(Value::String(self.node.val.into()));
+		c.emit(Opcode::Constant, &[i])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<BooleanLiteral> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		match self.node.value {
+			true => c.emit(Opcode::True, &[])?,
+			false => c.emit(Opcode::False, &[])?,
+		};
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<Identifier> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let Identifier { token, value } = self.node;
+		match c.symbol_table.resolve(&value) {
+			Some(symbol) => c.load_symbol(&symbol),
+			None => {
+				c.errors.push(CompileError::Undefined(
+					format!("Identifier {} not defined", value),
+					token.span,
+				));
+				c.emit(Opcode::Null, &[])?;
+				Ok(())
+			}
+		}
+	}
+}
+
+impl CompileNode for Compile<PrefixExpression> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let PrefixExpression {
+			token: _,
+			operator,
+			right,
+		} = self.node;
+		compile_node(*right, c)?;
+		match operator.as_str() {
+			"!" => c.emit(Opcode::Bang, &[])?,
+			"-" => c.emit(Opcode::Minus, &[])?,
+			_ => {
+				return Err(CompileError::Unsupported(format!(
+					"Unknown prefix operator {}",
+					operator
+				)))
+			}
+		};
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<InfixExpression> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let InfixExpression {
+			token: _,
+			left,
+			operator,
+			right,
+		} = self.node;
+		compile_node(*left, c)?;
+		compile_node(*right, c)?;
+		let op = infix_opcode(&operator).ok_or_else(|| {
+			CompileError::Unsupported(format!("Unknown infix operator {}", operator))
+		})?;
+		c.emit(op, &[])?;
+		Ok(())
+	}
+}
+
+fn infix_opcode(operator: &str) -> Option<Opcode> {
+	match operator {
+		"+" => Some(Opcode::Add),
+		"-" => Some(Opcode::Sub),
+		"*" => Some(Opcode::Mul),
+		"/" => Some(Opcode::Div),
+		"%" => Some(Opcode::Mod),
+		"==" => Some(Opcode::Equal),
+		"!=" => Some(Opcode::NotEqual),
+		">" => Some(Opcode::GreaterThan),
+		"<" => Some(Opcode::LessThan),
+		">=" => Some(Opcode::GreaterEqual),
+		"<=" => Some(Opcode::LessEqual),
+		_ => None,
+	}
+}
+
+// the left operand stays on the stack as the result when it decides, otherwise it is popped
+impl CompileNode for Compile<LogicalExpression> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let LogicalExpression {
+			token: _,
+			left,
+			operator,
+			right,
+		} = self.node;
+		let op = match operator.as_str() {
+			"&&" => Opcode::JumpNotTruthyOrPop,
+			"||" => Opcode::JumpTruthyOrPop,
+			_ => {
+				return Err(CompileError::Unsupported(format!(
+					"Unknown logical operator {}",
+					operator
+				)))
+			}
+		};
+		compile_node(*left, c)?;
+		let jump = c.emit(op, &[0])?;
+		compile_node(*right, c)?;
+		let end_pos = c.current_position();
+		c.change_operand(jump, end_pos)
+	}
+}
+
+impl CompileNode for Compile<IfExpression> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let IfExpression {
+			token: _,
+			condition,
+			consequence,
+			alternative,
+		} = self.node;
+		compile_node(*condition, c)?;
+		let jump_not_truthy = c.emit(Opcode::JumpNotTruthy, &[0])?;
+
+		c.compile_block_value(consequence)?;
+		let jump = c.emit(Opcode::Jump, &[0])?;
+
+		let alternative_pos = c.current_position();
+		c.change_operand(jump_not_truthy, alternative_pos)?;
+		match alternative {
+			Some(alternative) => c.compile_block_value(alternative)?,
+			None => {
+				c.emit(Opcode::Null, &[])?;
+			}
+		}
+
+		let end_pos = c.current_position();
+		c.change_operand(jump, end_pos)
+	}
+}
+
+impl CompileNode for Compile<FunctionLiteral> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let FunctionLiteral {
+			token: _,
+			params,
+			body,
+		} = self.node;
+		let num_params = params.len();
+
+		c.enter_scope();
+		params.into_iter().for_each(|p| {
+			c.symbol_table.define(p.value);
+		});
+		c.hoist_lets(&body.statements);
+		let body = c.compile_block_value(body);
+		let ret = c.emit(Opcode::ReturnValue, &[]);
+		let num_locals = c.symbol_table.num_definitions;
+		let free_symbols = mem::take(&mut c.symbol_table.free_symbols);
+		let scope = c.leave_scope();
+		body?;
+		ret?;
+
+		let captures = free_symbols
+			.iter()
+			.map(|s| match s.scope {
+				SymbolScope::Local => Capture::Local(s.index),
+				SymbolScope::Free => Capture::Free(s.index),
+				SymbolScope::Global | SymbolScope::Builtin => {
+					unreachable!("globals and builtins are never free")
+				}
+			})
+			.collect();
+		let function = CompiledFunction {
+			instructions: scope.instructions,
+			lines: scope.lines,
+			num_locals,
+			num_params,
+			captures,
+		};
+		let i = c.add_constant(Value::CompiledFunction(Rc::new(function)));
+		c.emit(Opcode::Closure, &[i, free_symbols.len()])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<CallExpression> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let CallExpression {
+			token: _,
+			function,
+			args,
+		} = self.node;
+		let num_args = args.len();
+		compile_node(*function, c)?;
+		args.into_iter().try_for_each(|a| compile_node(a, c))?;
+		c.emit(Opcode::Call, &[num_args])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<ArrayLiteral> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let len = self.node.mems.len();
+		self.node
+			.mems
+			.into_iter()
+			.try_for_each(|m| compile_node(m, c))?;
+		c.emit(Opcode::Array, &[len])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<HashLiteral> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let len = self.node.pairs.len() * 2;
+		self.node.pairs.into_iter().try_for_each(|(k, v)| {
+			compile_node(k, c)?;
+			compile_node(v, c)
+		})?;
+		c.emit(Opcode::Hash, &[len])?;
+		Ok(())
+	}
+}
+
+impl CompileNode for Compile<IndexExpression> {
+	fn compile(self, c: &mut Compiler) -> ResultCompile {
+		let IndexExpression {
+			tok: _,
+			left,
+			index,
+		} = self.node;
+		compile_node(*left, c)?;
+		compile_node(*index, c)?;
+		c.emit(Opcode::Index, &[])?;
+		Ok(())
+	}
+}
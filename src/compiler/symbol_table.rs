@@ -0,0 +1,141 @@
+use crate::eval::builtins::lookup_builtin;
+use std::collections::HashMap;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SymbolScope {
+	Global,
+	Local,
+	Builtin,
+	Free,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+	pub name: String,
+	pub scope: SymbolScope,
+	pub index: usize,
+}
+
+// the names declared in the body of a for loop, each with the binding it shadows
+#[derive(Default)]
+struct Block {
+	defined: Vec<(Symbol, Option<Symbol>)>,
+}
+
+#[derive(Default)]
+pub struct SymbolTable {
+	pub outer: Option<Box<SymbolTable>>,
+	pub free_symbols: Vec<Symbol>,
+	pub num_definitions: usize,
+	// names declared inside a block of the main program are locals rather than globals
+	pub num_block_locals: usize,
+	store: HashMap<String, Symbol>,
+	blocks: Vec<Block>,
+}
+
+impl SymbolTable {
+	pub fn new() -> Self {
+		SymbolTable::default()
+	}
+
+	pub fn new_enclosed(outer: SymbolTable) -> Self {
+		SymbolTable {
+			outer: Some(Box::new(outer)),
+			..SymbolTable::default()
+		}
+	}
+
+	// inside a block a name declared outside it is shadowed rather than reused
+	pub fn define(&mut self, name: String) -> Symbol {
+		if let Some(s) = self.store.get(&name) {
+			let in_block = match self.blocks.last() {
+				Some(b) => b.defined.iter().any(|(d, _)| d.name == name),
+				None => true,
+			};
+			if s.scope != SymbolScope::Free && in_block {
+				return s.clone();
+			}
+		}
+		let (scope, count) = match (&self.outer, self.blocks.is_empty()) {
+			(None, true) => (SymbolScope::Global, &mut self.num_definitions),
+			(None, false) => (SymbolScope::Local, &mut self.num_block_locals),
+			(Some(_), _) => (SymbolScope::Local, &mut self.num_definitions),
+		};
+		let symbol = Symbol {
+			name: name.clone(),
+			scope,
+			index: *count,
+		};
+		*count += 1;
+		let shadowed = self.store.insert(name, symbol.clone());
+		if let Some(b) = self.blocks.last_mut() {
+			b.defined.push((symbol.clone(), shadowed));
+		}
+		symbol
+	}
+
+	pub fn enter_block(&mut self) {
+		self.blocks.push(Block::default());
+	}
+
+	// forgets the names declared in the block and returns their symbols
+	pub fn leave_block(&mut self) -> Vec<Symbol> {
+		let block = self
+			.blocks
+			.pop()
+			.expect("left a block that was never entered");
+		block
+			.defined
+			.into_iter()
+			.rev()
+			.map(|(symbol, shadowed)| {
+				match shadowed {
+					Some(s) => self.store.insert(symbol.name.clone(), s),
+					None => self.store.remove(&symbol.name),
+				};
+				symbol
+			})
+			.collect()
+	}
+
+	// builtins shadow every other binding, the same as Env::get
+	pub fn resolve(&mut self, name: &str) -> Option<Symbol> {
+		match lookup_builtin(name) {
+			Some(index) => Some(Symbol {
+				name: name.to_string(),
+				scope: SymbolScope::Builtin,
+				index,
+			}),
+			None => self.resolve_scoped(name),
+		}
+	}
+
+	// whether the name resolves without declaring it free in the inner scopes
+	pub fn is_visible(&self, name: &str) -> bool {
+		lookup_builtin(name).is_some()
+			|| self.store.contains_key(name)
+			|| self.outer.as_ref().is_some_and(|o| o.is_visible(name))
+	}
+
+	fn resolve_scoped(&mut self, name: &str) -> Option<Symbol> {
+		if let Some(s) = self.store.get(name) {
+			return Some(s.clone());
+		}
+		let symbol = self.outer.as_mut()?.resolve_scoped(name)?;
+		match symbol.scope {
+			SymbolScope::Global | SymbolScope::Builtin => Some(symbol),
+			SymbolScope::Local | SymbolScope::Free => Some(self.define_free(symbol)),
+		}
+	}
+
+	fn define_free(&mut self, original: Symbol) -> Symbol {
+		let symbol = Symbol {
+			name: original.name.clone(),
+			scope: SymbolScope::Free,
+			index: self.free_symbols.len(),
+		};
+		self.store.insert(original.name.clone(), symbol.clone());
+		self.free_symbols.push(original);
+		symbol
+	}
+}
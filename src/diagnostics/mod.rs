@@ -0,0 +1,167 @@
+use crate::compiler::CompileError;
+use crate::eval::EvalError;
+use crate::parser::ParserError;
+use crate::token::Span;
+use crate::vm::VmError;
+
+pub struct Diagnostic {
+	pub kind: String,
+	pub message: String,
+	pub span: Option<Span>,
+	pub notes: Vec<String>,
+	pub help: Vec<String>,
+}
+
+impl Diagnostic {
+	pub fn new(kind: String, message: String) -> Self {
+		Diagnostic {
+			kind,
+			message,
+			span: None,
+			notes: Vec::new(),
+			help: Vec::new(),
+		}
+	}
+
+	pub fn with_span(mut self, span: Option<Span>) -> Self {
+		self.span = span;
+		self
+	}
+
+	pub fn with_note(mut self, note: &str) -> Self {
+		self.notes.push(note.to_string());
+		self
+	}
+
+	pub fn with_help(mut self, help: &str) -> Self {
+		self.help.push(help.to_string());
+		self
+	}
+
+	pub fn render(&self, source_name: &str, source: &str) -> String {
+		let mut out = format!("error[{}]: {}\n", self.kind, self.message);
+		let span = match self.span {
+			Some(span) => span,
+			None => return out + self.render_footer("").as_str(),
+		};
+
+		let gutter = " ".repeat(span.line.to_string().len());
+		out += format!(
+			"{}--> {}:{}:{}\n",
+			gutter, source_name, span.line, span.column
+		)
+		.as_str();
+		if let Some(text) = source.lines().nth(span.line.wrapping_sub(1)) {
+			let text = text.trim_end();
+			let indent = text
+				.chars()
+				.take(span.column.saturating_sub(1))
+				.map(|c| if c == '\t' { '\t' } else { ' ' })
+				.collect::<String>();
+			let remaining = text.chars().count().saturating_sub(indent.len());
+			let width = source
+				.get(span.start..span.end)
+				.map_or(span.end - span.start, |s| s.chars().count())
+				.clamp(1, remaining.max(1));
+			out += format!("{} |\n", gutter).as_str();
+			out += format!("{} | {}\n", span.line, text).as_str();
+			out += format!("{} | {}{}\n", gutter, indent, "^".repeat(width)).as_str();
+		}
+		out + self.render_footer(&gutter).as_str()
+	}
+
+	fn render_footer(&self, gutter: &str) -> String {
+		let notes = self.notes.iter().map(|n| ("note", n));
+		let help = self.help.iter().map(|h| ("help", h));
+		notes
+			.chain(help)
+			.map(|(label, text)| format!("{} = {}: {}\n", gutter, label, text))
+			.collect()
+	}
+}
+
+// the span of a whole source line without its indentation, for errors that only know the line
+pub fn line_span(source: &str, line: usize) -> Option<Span> {
+	let mut start = 0;
+	for (i, text) in source.split('\n').enumerate() {
+		if i + 1 == line {
+			let indent = text.len() - text.trim_start().len();
+			return Some(Span {
+				start: start + indent,
+				end: start + text.trim_end().len(),
+				line,
+				column: text[..indent].chars().count() + 1,
+			});
+		}
+		start += text.len() + 1;
+	}
+	None
+}
+
+impl From<&ParserError> for Diagnostic {
+	fn from(e: &ParserError) -> Self {
+		let d = Diagnostic::new(String::from("ParserError"), e.get_err_msg())
+			.with_span(Some(e.get_span()));
+		match e {
+			ParserError::UnexpectedEOF(..) => {
+				d.with_note("the source ended in the middle of a statement")
+			}
+			ParserError::IntParseError(..) => {
+				d.with_note("integer literals must fit in a signed 64-bit integer")
+			}
+			ParserError::NoPrefixParseFn(..) => {
+				d.with_note("this token cannot start an expression")
+			}
+			ParserError::UnexpectedToken(..)
+			| ParserError::NoInfixParseFn(..)
+			| ParserError::LexerError(..) => d,
+		}
+	}
+}
+
+impl From<&EvalError> for Diagnostic {
+	fn from(e: &EvalError) -> Self {
+		let inner = match e {
+			EvalError::Located(inner, _) => inner.as_ref(),
+			_ => e,
+		};
+		let d = match inner {
+			EvalError::ParserError(p) => return Diagnostic::from(p),
+			EvalError::Undefined(_) => Diagnostic::new(e.get_err_type(), e.get_err_msg())
+				.with_help("declare it with `let` before using it"),
+			EvalError::Unhashable(_) => Diagnostic::new(e.get_err_type(), e.get_err_msg())
+				.with_note("only String, Integer and Boolean values can be hash keys"),
+			_ => Diagnostic::new(e.get_err_type(), e.get_err_msg()),
+		};
+		d.with_span(e.get_span())
+	}
+}
+
+impl From<&CompileError> for Diagnostic {
+	fn from(e: &CompileError) -> Self {
+		match e {
+			CompileError::ParserError(p) => Diagnostic::from(p),
+			CompileError::Undefined(..) => Diagnostic::new(e.get_err_type(), e.get_err_msg())
+				.with_span(e.get_span())
+				.with_help("declare it with `let` before using it"),
+			CompileError::InvalidAssignment(..) => {
+				Diagnostic::new(e.get_err_type(), e.get_err_msg()).with_span(e.get_span())
+			}
+			CompileError::Unsupported(_) | CompileError::LimitExceeded(_) => {
+				Diagnostic::new(e.get_err_type(), e.get_err_msg())
+			}
+		}
+	}
+}
+
+impl From<&VmError> for Diagnostic {
+	fn from(e: &VmError) -> Self {
+		match e {
+			VmError::EvalError(e) => Diagnostic::from(e),
+			VmError::StackOverflow(_) => Diagnostic::new(e.get_err_type(), e.get_err_msg())
+				.with_note("this is usually caused by unbounded recursion"),
+			VmError::InvalidBytecode(_) => Diagnostic::new(e.get_err_type(), e.get_err_msg())
+				.with_note("the module is corrupt or was built by an incompatible compiler"),
+		}
+	}
+}
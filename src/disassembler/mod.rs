@@ -0,0 +1,149 @@
+use crate::code::{read_operands, Instructions, LineTable, Opcode, ASSIGN_OPERATORS};
+use crate::eval::builtins::BUILTINS;
+use crate::module::Module;
+use crate::object::*;
+
+pub fn disassemble(module: &Module) -> String {
+	let bytecode = &module.bytecode;
+	let source = module
+		.debug
+		.as_ref()
+		.map(|d| d.source.lines().collect::<Vec<&str>>());
+	let source = source.as_deref();
+	let name = module
+		.debug
+		.as_ref()
+		.map_or("<unknown source>", |d| d.source_name.as_str());
+
+	let mut out = format!("== main: {} ==\n", name);
+	out += disassemble_instructions(
+		&bytecode.instructions,
+		&bytecode.lines,
+		&bytecode.constants,
+		source,
+	)
+	.as_str();
+
+	bytecode
+		.constants
+		.iter()
+		.enumerate()
+		.filter_map(|(i, c)| match c {
+			Value::CompiledFunction(f) => Some((i, f)),
+			_ => None,
+		})
+		.for_each(|(i, f)| {
+			let captures = f
+				.captures
+				.iter()
+				.map(|c| match c {
+					Capture::Local(l) => format!(", captures local {}", l),
+					Capture::Free(i) => format!(", captures free {}", i),
+				})
+				.collect::<String>();
+			out += format!(
+				"\n== constant {}: function ({} params, {} locals{}) ==\n",
+				i, f.num_params, f.num_locals, captures
+			)
+			.as_str();
+			out += disassemble_instructions(&f.instructions, &f.lines, &bytecode.constants, source)
+				.as_str();
+		});
+	out
+}
+
+fn disassemble_instructions(
+	ins: &Instructions,
+	lines: &LineTable,
+	constants: &[Value],
+	source: Option<&[&str]>,
+) -> String {
+	let ins = &ins.0;
+	let mut lines = lines.iter().peekable();
+	let mut out = String::new();
+	let mut i = 0;
+	while i < ins.len() {
+		while let Some((_, line)) = lines.next_if(|(pos, _)| *pos <= i) {
+			if let Some(text) = source.and_then(|s| s.get(line.wrapping_sub(1))) {
+				out += format!("{:>4} | {}\n", line, text.trim()).as_str();
+			}
+		}
+
+		let op = match Opcode::from_byte(ins[i]) {
+			Some(op) => op,
+			None => {
+				out += format!("{:04} <unknown opcode {}>\n", i, ins[i]).as_str();
+				i += 1;
+				continue;
+			}
+		};
+		let def = op.definition();
+		let width: usize = def.operand_widths.iter().sum();
+		if i + 1 + width > ins.len() {
+			out += format!("{:04} {} <missing operands>\n", i, def.name).as_str();
+			break;
+		}
+		let (operands, read) = read_operands(&def, &ins[i + 1..]);
+		let operand_str = operands
+			.iter()
+			.map(|o| o.to_string())
+			.collect::<Vec<String>>()
+			.join(" ");
+
+		let listing = format!(
+			"{:04} {:<16} {:<6} {}",
+			i,
+			def.name,
+			operand_str,
+			annotate(op, &operands, constants)
+		);
+		out += listing.trim_end();
+		out.push('\n');
+		i += 1 + read;
+	}
+	out
+}
+
+fn annotate(op: Opcode, operands: &[usize], constants: &[Value]) -> String {
+	let operand = match operands.first() {
+		Some(o) => *o,
+		None => return String::new(),
+	};
+	match op {
+		Opcode::Constant => match constants.get(operand) {
+			Some(c) => format!("({})", describe_constant(c)),
+			None => String::from("(<missing constant>)"),
+		},
+		Opcode::Closure => match constants.get(operand) {
+			Some(c) => format!(
+				"({}, {} free)",
+				describe_constant(c),
+				operands.get(1).copied().unwrap_or(0)
+			),
+			None => String::from("(<missing constant>)"),
+		},
+		Opcode::GetBuiltin => match BUILTINS.get(operand) {
+			Some((name, _)) => format!("({})", name),
+			None => String::from("(<unknown builtin>)"),
+		},
+		Opcode::Jump
+		| Opcode::JumpNotTruthy
+		| Opcode::JumpNotTruthyOrPop
+		| Opcode::JumpTruthyOrPop
+		| Opcode::LoopJump
+		| Opcode::IterNext => format!("(-> {:04})", operand),
+		Opcode::SetIndex => match ASSIGN_OPERATORS.get(operand) {
+			Some(op) => format!("({})", op),
+			None => String::from("(<unknown operator>)"),
+		},
+		_ => String::new(),
+	}
+}
+
+fn describe_constant(constant: &Value) -> String {
+	match constant {
+		Value::String(val) => format!("{:?}", val),
+		Value::CompiledFunction(_) => String::from("<function>"),
+		_ => constant.inspect_obj(),
+	}
+}
@@ -1,183 +1,145 @@
 use std::collections::HashMap;
-use std::rc::Rc;
 
 use crate::eval::*;
 use crate::object::*;
 
-pub fn get_builtins() -> HashMap<String, Box<dyn Obj>> {
-	let mut hm = HashMap::new();
-	hm.insert(
-		String::from("len"),
-		Box::new(Builtin {
-			func: Rc::new(Box::new(len_fn)),
-		}) as Box<dyn Obj>,
-	);
-	hm.insert(
-		String::from("first"),
-		Box::new(Builtin {
-			func: Rc::new(Box::new(first_fn)),
-		}) as Box<dyn Obj>,
-	);
-	hm.insert(
-		String::from("last"),
-		Box::new(Builtin {
-			func: Rc::new(Box::new(last_fn)),
-		}) as Box<dyn Obj>,
-	);
-	hm.insert(
-		String::from("rest"),
-		Box::new(Builtin {
-			func: Rc::new(Box::new(rest_fn)),
-		}) as Box<dyn Obj>,
-	);
-	hm.insert(
-		String::from("push"),
-		Box::new(Builtin {
-			func: Rc::new(Box::new(push_fn)),
-		}) as Box<dyn Obj>,
-	);
-	hm.insert(
-		String::from("puts"),
-		Box::new(Builtin {
-			func: Rc::new(Box::new(puts_fn)),
-		}) as Box<dyn Obj>,
-	);
-	hm
+pub type BuiltinFunc = fn(Vec<Value>) -> Result<Value, EvalError>;
+
+pub const BUILTINS: [(&str, BuiltinFunc); 7] = [
+	("len", len_fn),
+	("first", first_fn),
+	("last", last_fn),
+	("rest", rest_fn),
+	("push", push_fn),
+	("puts", puts_fn),
+	("range", range_fn),
+];
+
+pub fn get_builtins() -> HashMap<String, Value> {
+	BUILTINS
+		.iter()
+		.map(|(name, func)| (name.to_string(), Value::Builtin(*func)))
+		.collect()
 }
 
-fn len_fn(input: Vec<Box<dyn Obj>>) -> Result<Box<dyn Obj>, EvalError> {
-	println!("called len");
-	let input = match input.as_slice() {
-		[i] => Ok(i.clone()),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"Len requires one arg",
-		))),
-	}?;
-	match input.get_type() {
-		ObjType::String => len_str(input),
-		ObjType::Array => len_arr(input),
-		_ => Err(EvalError::UnexpectedNode(String::from(
-			"Argument is not of the correct type",
-		))),
-	}
-}
-
-fn len_str(input: Box<dyn Obj>) -> Result<Box<dyn Obj>, EvalError> {
-	let input = input.as_any().downcast_ref::<StringObj>().unwrap();
-
-	let val: i64 = input.val.len().try_into().unwrap();
-
-	Ok(Box::new(Integer { val }))
+pub fn lookup_builtin(name: &str) -> Option<usize> {
+	BUILTINS.iter().position(|(n, _)| *n == name)
 }
 
-fn len_arr(input: Box<dyn Obj>) -> Result<Box<dyn Obj>, EvalError> {
-	let input = input.as_any().downcast_ref::<Array>().unwrap();
-
-	let val: i64 = input.mems.len().try_into().unwrap();
-
-	Ok(Box::new(Integer { val }))
+fn check_arity(name: &str, input: &[Value], expected: usize) -> Result<(), EvalError> {
+	if input.len() == expected {
+		return Ok(());
+	}
+	let noun = match expected {
+		1 => "argument",
+		_ => "arguments",
+	};
+	Err(EvalError::IncorrectArgs(format!(
+		"{} expects {} {}, got {}",
+		name,
+		expected,
+		noun,
+		input.len()
+	)))
 }
 
-fn first_fn(input: Vec<Box<dyn Obj>>) -> Result<Box<dyn Obj>, EvalError> {
-	let input = match input.as_slice() {
-		[i] => Ok(i),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"Len requires one arg",
+fn expect_array<'a>(name: &str, input: &'a Value) -> Result<&'a Array, EvalError> {
+	match input {
+		Value::Array(a) => Ok(a),
+		_ => Err(EvalError::IncorrectArgs(format!(
+			"{} expects an Array argument, got {}",
+			name,
+			input.get_type().string()
 		))),
-	}?;
+	}
+}
 
-	match input.get_type() {
-		ObjType::Array => Ok(()),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"first requires array argument",
-		))),
-	}?;
+fn len_fn(input: Vec<Value>) -> Result<Value, EvalError> {
+	check_arity("len", &input, 1)?;
+	let len = match &input[0] {
+		Value::String(s) => s.chars().count(),
+		Value::Array(a) => a.mems.borrow().len(),
+		v => {
+			return Err(EvalError::IncorrectArgs(format!(
+				"len expects a String or Array argument, got {}",
+				v.get_type().string()
+			)))
+		}
+	};
+	Ok(Value::Integer(len as i64))
+}
 
-	input.as_any()
-		.downcast_ref::<Array>()
-		.unwrap()
+fn first_fn(input: Vec<Value>) -> Result<Value, EvalError> {
+	check_arity("first", &input, 1)?;
+	expect_array("first", &input[0])?
 		.mems
+		.borrow()
 		.front()
-		.ok_or(EvalError::IncorrectArgs(String::from(
-			"array requires atleast one argument",
+		.ok_or(EvalError::OutOfBounds(String::from(
+			"first expects a non-empty Array",
 		)))
 		.cloned()
 }
-fn last_fn(input: Vec<Box<dyn Obj>>) -> Result<Box<dyn Obj>, EvalError> {
-	let input = match input.as_slice() {
-		[i] => Ok(i),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"Len requires one arg",
-		))),
-	}?;
-
-	match input.get_type() {
-		ObjType::Array => Ok(()),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"first requires array argument",
-		))),
-	}?;
 
-	input.as_any()
-		.downcast_ref::<Array>()
-		.unwrap()
+fn last_fn(input: Vec<Value>) -> Result<Value, EvalError> {
+	check_arity("last", &input, 1)?;
+	expect_array("last", &input[0])?
 		.mems
+		.borrow()
 		.back()
-		.ok_or(EvalError::IncorrectArgs(String::from(
-			"array requires atleast one argument",
+		.ok_or(EvalError::OutOfBounds(String::from(
+			"last expects a non-empty Array",
 		)))
 		.cloned()
 }
 
-fn rest_fn(input: Vec<Box<dyn Obj>>) -> Result<Box<dyn Obj>, EvalError> {
-	let input = match input.as_slice() {
-		[i] => Ok(i),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"Len requires one arg",
-		))),
-	}?;
-	match input.get_type() {
-		ObjType::Array => Ok(()),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"first requires array argument",
-		))),
-	}?;
-	let mut input = input.as_any().downcast_ref::<Array>().unwrap().mems.clone();
-	input.pop_front()
-		.ok_or(EvalError::IncorrectArgs(String::from(
-			"array requires atleast one argument",
-		)))?;
-	Ok(Box::new(Array {
-		mems: input.clone(),
-	}))
+fn rest_fn(input: Vec<Value>) -> Result<Value, EvalError> {
+	check_arity("rest", &input, 1)?;
+	let mut mems = expect_array("rest", &input[0])?.mems.borrow().clone();
+	mems.pop_front().ok_or(EvalError::OutOfBounds(String::from(
+		"rest expects a non-empty Array",
+	)))?;
+	Ok(Value::Array(Array::new(mems)))
 }
 
-fn push_fn(input: Vec<Box<dyn Obj>>) -> Result<Box<dyn Obj>, EvalError> {
-	let (&ref input, &ref object) = match input.as_slice() {
-		[i, o] => Ok((i, o)),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"Len requires one arg",
-		))),
-	}?;
+fn push_fn(input: Vec<Value>) -> Result<Value, EvalError> {
+	check_arity("push", &input, 2)?;
+	let mut mems = expect_array("push", &input[0])?.mems.borrow().clone();
+	mems.push_back(input[1].clone());
+	Ok(Value::Array(Array::new(mems)))
+}
 
-	match input.get_type() {
-		ObjType::Array => Ok(()),
-		_ => Err(EvalError::IncorrectArgs(String::from(
-			"first requires array argument",
+fn expect_integer(name: &str, input: &Value) -> Result<i64, EvalError> {
+	match input {
+		Value::Integer(val) => Ok(*val),
+		_ => Err(EvalError::IncorrectArgs(format!(
+			"{} expects Integer arguments, got {}",
+			name,
+			input.get_type().string()
 		))),
-	}?;
-
-	let mut input = input.as_any().downcast_ref::<Array>().unwrap().mems.clone();
-
-	input.push_back(object.clone());
+	}
+}
 
-	Ok(Box::new(Array {
-		mems: input.clone(),
-	}))
+// range(end) counts from 0, range(start, end) from start, end is never included
+fn range_fn(input: Vec<Value>) -> Result<Value, EvalError> {
+	let (start, end) = match input.as_slice() {
+		[end] => (0, expect_integer("range", end)?),
+		[start, end] => (
+			expect_integer("range", start)?,
+			expect_integer("range", end)?,
+		),
+		_ => {
+			return Err(EvalError::IncorrectArgs(format!(
+				"range expects 1 or 2 arguments, got {}",
+				input.len()
+			)))
+		}
+	};
+	Ok(Value::Range(start, end))
 }
 
-fn puts_fn(input: Vec<Box<dyn Obj>>) -> Result<Box<dyn Obj>, EvalError> {
+fn puts_fn(input: Vec<Value>) -> Result<Value, EvalError> {
 	input.into_iter()
 		.for_each(|o| println!("{}", o.inspect_obj()));
-	Ok(Box::new(Null{}))
+	Ok(Value::Null)
 }
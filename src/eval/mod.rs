@@ -1,11 +1,32 @@
 pub mod builtins;
 
 use crate::ast::*;
+use crate::object::bigint::BigInt;
+use crate::token::Span;
 use crate::{object::*, parser::ParserError};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::iter::zip;
+use std::rc::Rc;
+
+type ResultObj = Result<Value, EvalError>;
+type ResultEval = Result<Value, Unwind>;
+
+// a return, break or continue leaves every expression it is nested in the same
+// way an error does, until the function call, loop or program that handles it
+#[derive(Debug)]
+pub enum Unwind {
+	Return(Value),
+	Break,
+	Continue,
+	Error(EvalError),
+}
+
+impl From<EvalError> for Unwind {
+	fn from(e: EvalError) -> Self {
+		Unwind::Error(e)
+	}
+}
 
-type ResultObj = Result<Box<dyn Obj>, EvalError>;
 #[derive(Debug)]
 pub enum EvalError {
 	ParserError(ParserError),
@@ -14,6 +35,13 @@ pub enum EvalError {
 	IncorrectArgs(String),
 	OutOfBounds(String),
 	Unhashable(String),
+	DivisionByZero {
+		operator: String,
+		left: Value,
+		right: Value,
+	},
+	InvalidAssignment(String),
+	Located(Box<EvalError>, Span),
 }
 
 impl EvalError {
@@ -24,7 +52,10 @@ impl EvalError {
 			EvalError::Undefined(_) => String::from("Undefined"),
 			EvalError::IncorrectArgs(_) => String::from("IncorrectArgs"),
 			EvalError::OutOfBounds(_) => String::from("OutOfBounds"),
-			EvalError::Unhashable(_) => todo!(),
+			EvalError::Unhashable(_) => String::from("Unhashable"),
+			EvalError::DivisionByZero { .. } => String::from("DivisionByZero"),
+			EvalError::InvalidAssignment(_) => String::from("InvalidAssignment"),
+			EvalError::Located(e, _) => e.get_err_type(),
 		}
 	}
 	pub fn get_err_msg(&self) -> String {
@@ -34,111 +65,221 @@ impl EvalError {
 			EvalError::Undefined(m) => m.to_string(),
 			EvalError::IncorrectArgs(m) => m.to_string(),
 			EvalError::OutOfBounds(m) => m.to_string(),
-			EvalError::Unhashable(_) => todo!(),
+			EvalError::Unhashable(m) => m.to_string(),
+			EvalError::DivisionByZero {
+				operator,
+				left,
+				right,
+			} => format!(
+				"Division by zero in {} {} {}",
+				left.inspect_obj(),
+				operator,
+				right.inspect_obj()
+			),
+			EvalError::InvalidAssignment(m) => m.to_string(),
+			EvalError::Located(e, _) => e.get_err_msg(),
+		}
+	}
+	pub fn get_span(&self) -> Option<Span> {
+		match self {
+			EvalError::ParserError(e) => Some(e.get_span()),
+			EvalError::Located(_, s) => Some(*s),
+			_ => None,
+		}
+	}
+	// keeps the innermost location, which is the closest to the cause
+	pub fn located(self, span: Span) -> EvalError {
+		match self.get_span() {
+			Some(_) => self,
+			None => EvalError::Located(Box::new(self), span),
 		}
 	}
 }
 
 pub trait EvalNode {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj;
+	fn eval(self, env: &mut Env) -> ResultEval;
+}
+
+// the tree is only borrowed, so loop and function bodies run again without being copied
+pub struct Eval<'a, N: Node> {
+	pub node: &'a N,
 }
 
-pub struct Eval<N: Node> {
-	pub node: N,
+pub fn eval_node<'a, N: Node>(node: &'a N, env: &mut Env) -> ResultEval
+where
+	Eval<'a, N>: EvalNode,
+{
+	Eval { node }.eval(env)
+}
+
+impl EvalNode for Eval<'_, Stmt> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		match self.node {
+			Stmt::Let(n) => eval_node(n, env),
+			Stmt::Return(n) => eval_node(n, env),
+			Stmt::Expression(n) => eval_node(n, env),
+			Stmt::While(n) => eval_node(n, env),
+			Stmt::For(n) => eval_node(n, env),
+			Stmt::Break(n) => eval_node(n, env),
+			Stmt::Continue(n) => eval_node(n, env),
+		}
+	}
 }
 
-impl EvalNode for Eval<HashLiteral> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
+impl EvalNode for Eval<'_, Expr> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		match self.node {
+			Expr::Identifier(n) => eval_node(n, env),
+			Expr::Integer(n) => eval_node(n, env),
+			Expr::Float(n) => eval_node(n, env),
+			Expr::String(n) => eval_node(n, env),
+			Expr::Boolean(n) => eval_node(n, env),
+			Expr::Array(n) => eval_node(n, env),
+			Expr::Hash(n) => eval_node(n, env),
+			Expr::Index(n) => eval_node(n, env),
+			Expr::Prefix(n) => eval_node(n, env),
+			Expr::Infix(n) => eval_node(n, env),
+			Expr::Logical(n) => eval_node(n, env),
+			Expr::Assign(n) => eval_node(n, env),
+			Expr::IndexAssign(n) => eval_node(n, env),
+			Expr::If(n) => eval_node(n, env),
+			Expr::Function(n) => eval_node(n, env),
+			Expr::Call(n) => eval_node(n, env),
+		}
+	}
+}
+
+impl EvalNode for Eval<'_, HashLiteral> {
+	fn eval(self, env: &mut Env) -> ResultEval {
 		let values = self
 			.node
 			.pairs
-			.into_iter()
+			.iter()
 			.map(|(k, v)| {
-				match (k.into_eval_node().eval(env), v.into_eval_node().eval(env)) {
+				match (eval_node(k, env), eval_node(v, env)) {
 					(Ok(k2), Ok(v2)) => Ok(HashPair(k2, v2)),
 					(Err(e), _) => Err(e),
 					(_, Err(e)) => Err(e),
 				}
 			})
-			.collect::<Result<Vec<HashPair>, EvalError>>()?;
-
-		let keys = values
-			.clone()
-			.into_iter()
-			.map(|HashPair(k, v)| try_hash(k))
-			.collect::<Result<Vec<HashKey>, EvalError>>()?;
-
-		let pairs = zip(keys.into_iter(), values.into_iter()).collect();
+			.collect::<Result<Vec<HashPair>, Unwind>>()?;
 
-		Ok(Box::new(Hash { pairs }))
+		Ok(build_hash(values)?)
 	}
 }
 
-fn try_hash(o: Box<dyn Obj>) -> Result<HashKey, EvalError> {
-	match o.get_type() {
-		ObjType::String => Ok(o.as_any().downcast_ref::<StringObj>().unwrap().hash_key()),
-		ObjType::Integer => Ok(o.as_any().downcast_ref::<Integer>().unwrap().hash_key()),
-		ObjType::Boolean => Ok(o.as_any().downcast_ref::<Boolean>().unwrap().hash_key()),
-		_ => Err(EvalError::Unhashable(String::from(
-			"Cannot hash key value must be string, integer or boolean",
-		))),
-	}
+pub fn build_hash(values: Vec<HashPair>) -> ResultObj {
+	let pairs = values
+		.into_iter()
+		.map(|pair| Ok((try_hash(&pair.0)?, pair)))
+		.collect::<Result<HashMap<HashKey, HashPair>, EvalError>>()?;
+
+	Ok(Value::Hash(Hash::new(pairs)))
 }
 
-impl EvalNode for Eval<IndexExpression> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
-		let IndexExpression {
-			tok: _,
-			left,
-			index,
-		} = self.node;
-		let index = index.into_eval_node().eval(env)?;
+fn try_hash(o: &Value) -> Result<HashKey, EvalError> {
+	o.hash_key().ok_or(EvalError::Unhashable(String::from(
+		"Cannot hash key value must be string, integer or boolean",
+	)))
+}
 
-		let left = left.into_eval_node().eval(env)?;
-		match left.get_type() {
-			ObjType::Array => get_indexed_array(left, index),
-			ObjType::Hash => get_indexed_hash(left, index),
-			_ => Err(EvalError::UnexpectedNode(String::from("Expected Array Object"))),
-		}
+impl EvalNode for Eval<'_, IndexExpression> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let IndexExpression { tok, left, index } = self.node;
+		let left = eval_node(left.as_ref(), env)?;
+		let index = eval_node(index.as_ref(), env)?;
+		Ok(eval_index(left, index).map_err(|e| e.located(tok.span))?)
 	}
 }
 
-fn get_indexed_array(a: Box<dyn Obj>, i: Box<dyn Obj>) -> Result<Box<dyn Obj>, EvalError> {
-	let i = i
-		.as_any()
-		.downcast_ref::<Integer>()
-		.ok_or(EvalError::UnexpectedNode(String::from(
-			"Expected Integer Object",
-		)))?;
-
-	let a = a
-		.as_any()
-		.downcast_ref::<Array>()
-		.ok_or(EvalError::UnexpectedNode(String::from(
+pub fn eval_index(left: Value, index: Value) -> ResultObj {
+	match &left {
+		Value::Array(a) => get_indexed_array(a, &index),
+		Value::Hash(h) => get_indexed_hash(h, &index),
+		_ => Err(EvalError::UnexpectedNode(String::from(
 			"Expected Array Object",
-		)))?;
+		))),
+	}
+}
+
+fn get_indexed_array(a: &Array, i: &Value) -> ResultObj {
+	let i = array_index(i)?;
 
-	a.mems.get(i
-		.val
-		.try_into()
-		.map_err(|_| EvalError::OutOfBounds(String::from("Invalid Integer for Index")))?)
+	a.mems
+		.borrow()
+		.get(i)
 		.ok_or(EvalError::OutOfBounds(String::from(
 			"Index is out of bounds",
 		)))
 		.cloned()
 }
 
-fn get_indexed_hash(h: Box<dyn Obj>, i: Box<dyn Obj>) -> Result<Box<dyn Obj>, EvalError> {
-	let h = h
-		.as_any()
-		.downcast_ref::<Hash>()
-		.ok_or(EvalError::UnexpectedNode(String::from(
-			"Expected Hash Object",
-		)))?;
+fn array_index(i: &Value) -> Result<usize, EvalError> {
+	match i {
+		Value::Integer(val) => (*val)
+			.try_into()
+			.map_err(|_| EvalError::OutOfBounds(String::from("Invalid Integer for Index"))),
+		Value::BigInteger(_) => Err(EvalError::OutOfBounds(String::from(
+			"Index is out of bounds",
+		))),
+		_ => Err(EvalError::UnexpectedNode(String::from(
+			"Expected Integer Object",
+		))),
+	}
+}
+
+// arrays can only be assigned within their bounds, hashes insert or replace the key
+fn set_index(left: &Value, index: Value, val: Value) -> Result<(), EvalError> {
+	match left {
+		Value::Array(a) if !contains(&val, Rc::as_ptr(&a.mems) as *const ()) => {
+			let i = array_index(&index)?;
+			let mut mems = a.mems.borrow_mut();
+			*mems.get_mut(i).ok_or(EvalError::OutOfBounds(String::from(
+				"Index is out of bounds",
+			)))? = val;
+			Ok(())
+		}
+		Value::Hash(h) if !contains(&val, Rc::as_ptr(&h.pairs) as *const ()) => {
+			let key = try_hash(&index)?;
+			h.pairs.borrow_mut().insert(key, HashPair(index, val));
+			Ok(())
+		}
+		Value::Array(_) | Value::Hash(_) => Err(EvalError::InvalidAssignment(format!(
+			"Cannot store {} inside itself",
+			left.get_type().string()
+		))),
+		_ => Err(EvalError::UnexpectedNode(format!(
+			"Cannot assign to an index of {}",
+			left.get_type().string()
+		))),
+	}
+}
+
+// a container stored inside itself could never be printed or compared
+fn contains(val: &Value, target: *const ()) -> bool {
+	match val {
+		Value::Array(a) => {
+			Rc::as_ptr(&a.mems) as *const () == target
+				|| a.mems.borrow().iter().any(|m| contains(m, target))
+		}
+		Value::Hash(h) => {
+			Rc::as_ptr(&h.pairs) as *const () == target
+				|| h.pairs
+					.borrow()
+					.values()
+					.any(|HashPair(_, v)| contains(v, target))
+		}
+		_ => false,
+	}
+}
 
-	let i = get_hash_key(i)?;
+fn get_indexed_hash(h: &Hash, i: &Value) -> ResultObj {
+	let i = i.hash_key().ok_or(EvalError::UnexpectedNode(String::from(
+		"Expected Index Obj",
+	)))?;
 
 	let h = h.pairs
+		.borrow()
 		.get(&i)
 		.ok_or(EvalError::OutOfBounds(String::from(
 			"Invalid Index for hash map",
@@ -147,383 +288,579 @@ fn get_indexed_hash(h: Box<dyn Obj>, i: Box<dyn Obj>) -> Result<Box<dyn Obj>, Ev
 	Ok(h.1)
 }
 
-fn get_hash_key(i: Box<dyn Obj>) -> Result<HashKey, EvalError> {
-	match i.get_type() {
-		ObjType::Integer => get_hashkey_integer(i),
-		ObjType::String => get_hashkey_string(i),
-		ObjType::Boolean => get_hashkey_boolean(i),
-		_ => Err(EvalError::UnexpectedNode(String::from(
-			"Expected Index Obj",
-		))),
+impl EvalNode for Eval<'_, Program> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let Program { statements, errors } = self.node;
+		if let Some(e) = errors.first() {
+			return Err(EvalError::ParserError(e.clone()).into());
+		}
+		for s in statements {
+			match eval_node(s, env) {
+				Ok(_) => (),
+				Err(Unwind::Return(val)) => return Ok(val),
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(Value::Null)
 	}
 }
 
-fn get_hashkey_integer(i: Box<dyn Obj>) -> Result<HashKey, EvalError> {
-	Ok(i.as_any().downcast_ref::<Integer>().unwrap().hash_key())
-}
-
-fn get_hashkey_string(i: Box<dyn Obj>) -> Result<HashKey, EvalError> {
-	Ok(i.as_any().downcast_ref::<StringObj>().unwrap().hash_key())
-}
-
-fn get_hashkey_boolean(i: Box<dyn Obj>) -> Result<HashKey, EvalError> {
-	Ok(i.as_any().downcast_ref::<Boolean>().unwrap().hash_key())
-}
-
-impl EvalNode for Eval<Program> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
-		self.node
-			.statements
-			.into_iter()
-			.map(|s| -> ResultObj {
-				match s {
-					Err(e) => Err(EvalError::ParserError(e)),
-					Ok(o) => o.into_eval_node().eval(env),
-				}
-			})
-			.find(|n| match n.get_type() {
-				ObjType::ReturnValue => true,
-				ObjType::Error => true,
-				_ => false,
-			})
-			.unwrap_or(Ok(Box::new(Null {})))
+impl EvalNode for Eval<'_, StringLiteral> {
+	fn eval(self, _env: &mut Env) -> ResultEval {
+		Ok(Value::String(self.node.val.as_str().into()))
 	}
 }
 
-impl EvalNode for Eval<StringLiteral> {
-	fn eval(self: Box<Self>, _env: &mut Env) -> ResultObj {
-		Ok(Box::new(StringObj { val: self.node.val }))
+impl EvalNode for Eval<'_, ExpressionStatement> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		eval_node(&self.node.expression, env)
 	}
 }
 
-impl EvalNode for Eval<ExpressionStatement> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
-		(*self).node.expression.into_eval_node().eval(env)
+impl EvalNode for Eval<'_, IntegerLiteral> {
+	fn eval(self, _env: &mut Env) -> ResultEval {
+		Ok(Value::Integer(self.node.value))
 	}
 }
 
-impl EvalNode for Eval<IntegerLiteral> {
-	fn eval(self: Box<Self>, _env: &mut Env) -> ResultObj {
-		Ok(Box::new(Integer {
-			val: self.node.value,
-		}))
+impl EvalNode for Eval<'_, FloatLiteral> {
+	fn eval(self, _env: &mut Env) -> ResultEval {
+		Ok(Value::Float(self.node.value))
 	}
 }
 
-impl EvalNode for Eval<CallExpression> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
+impl EvalNode for Eval<'_, CallExpression> {
+	fn eval(self, env: &mut Env) -> ResultEval {
 		let CallExpression {
-			token: _,
+			token,
 			function,
 			args,
 		} = self.node;
-		let function = function.into_eval_node().eval(env)?;
-		let args: Result<Vec<_>, EvalError> = args
-			.into_iter()
-			.map(|a| a.into_eval_node().eval(env))
-			.collect();
+		let function = eval_node(function.as_ref(), env)?;
+		let args: Result<Vec<_>, Unwind> = args.iter().map(|a| eval_node(a, env)).collect();
 		let args = args?;
-		if function.get_type() == ObjType::Function {
-			return apply_function_native(function, args);
-		}
-		if function.get_type() == ObjType::BuiltinFunction {
-			return apply_function_builtin(function, args);
-		}
-		Err(EvalError::UnexpectedNode(String::from(
-			"Expected Function Identifier",
-		)))
+		let result = match function {
+			Value::Function(f) => apply_function_native(&f, args),
+			Value::Builtin(f) => f(args),
+			_ => Err(EvalError::UnexpectedNode(String::from(
+				"Expected Function Identifier",
+			))),
+		};
+		Ok(result.map_err(|e| e.located(token.span))?)
 	}
 }
 
-fn apply_function_builtin(function: Box<dyn Obj>, args: Vec<Box<dyn Obj>>) -> ResultObj {
-	let function =
-		function.as_any()
-			.downcast_ref::<Builtin>()
-			.ok_or(EvalError::UnexpectedNode(String::from(
-				"Should be a function here",
-			)))?;
-
-	(function.func)(args)
-}
-
-fn apply_function_native(function: Box<dyn Obj>, args: Vec<Box<dyn Obj>>) -> ResultObj {
-	let function =
-		function.as_any()
-			.downcast_ref::<Function>()
-			.ok_or(EvalError::UnexpectedNode(String::from(
-				"Should be a function here",
-			)))?;
+fn apply_function_native(function: &Function, args: Vec<Value>) -> ResultObj {
 	let Function { params, body, env } = function;
 
+	if params.len() != args.len() {
+		return Err(EvalError::IncorrectArgs(format!(
+			"Function expects {} args, got {}",
+			params.len(),
+			args.len()
+		)));
+	}
+
 	let env = &mut Env::new(Some(Box::new(env.clone())));
-	zip(params.into_iter(), args.into_iter()).for_each(|(p, a)| env.set(p.value.clone(), a));
+	zip(params, args).for_each(|(p, a)| env.set(p.value.clone(), a));
 
-	Box::new(Eval { node: body.clone() }).eval(env)
+	match eval_node(body, env) {
+		Ok(val) | Err(Unwind::Return(val)) => Ok(val),
+		Err(Unwind::Error(e)) => Err(e),
+		Err(Unwind::Break | Unwind::Continue) => Err(EvalError::UnexpectedNode(String::from(
+			"break or continue outside of a loop",
+		))),
+	}
 }
 
-impl EvalNode for Eval<IfExpression> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
+impl EvalNode for Eval<'_, IfExpression> {
+	fn eval(self, env: &mut Env) -> ResultEval {
 		let IfExpression {
 			token: _,
 			condition,
 			consequence,
 			alternative,
-		} = (*self).node;
-
-		let condition = condition
-			.into_eval_node()
-			.eval(env)?
-			.as_any()
-			.downcast_ref::<Boolean>()
-			.ok_or(EvalError::UnexpectedNode(String::from(
-				"Condition musst evaluate to a boolean value",
-			)))?
-			.val;
-
-		if condition {
-			return Box::new(consequence).into_eval_node().eval(env);
+		} = self.node;
+
+		if eval_node(condition.as_ref(), env)?.is_truthy() {
+			return eval_node(consequence, env);
 		}
 		if let Some(n) = alternative {
-			return Box::new(n).into_eval_node().eval(env);
+			return eval_node(n, env);
 		}
-		Ok(Box::new(Null {}))
+		Ok(Value::Null)
 	}
 }
-impl EvalNode for Eval<FunctionLiteral> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
+
+impl EvalNode for Eval<'_, FunctionLiteral> {
+	fn eval(self, env: &mut Env) -> ResultEval {
 		let FunctionLiteral {
 			token: _,
 			params,
 			body,
 		} = self.node;
-		Ok(Box::new(Function {
-			params,
-			body,
+		Ok(Value::Function(Rc::new(Function {
+			params: params.clone(),
+			body: body.clone(),
 			env: env.clone(),
-		}))
+		})))
 	}
 }
-impl EvalNode for Eval<BlockStatement> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
-		self.node
-			.statements
-			.into_iter()
-			.scan(env, scan_node)
-			.find(|n| n.as_ref().unwrap().get_type() == ObjType::ReturnValue)
-			.unwrap_or(Ok(Box::new(Null {})))
+// a block is worth its last statement
+impl EvalNode for Eval<'_, BlockStatement> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let mut last = Value::Null;
+		for s in &self.node.statements {
+			last = eval_node(s, env)?;
+		}
+		Ok(last)
 	}
 }
 
-fn scan_node(
-	env: &mut &mut Env,
-	node: Result<Box<dyn Node + 'static>, ParserError>,
-) -> Option<ResultObj> {
-	let res = match node {
-		Err(e) => Err(EvalError::ParserError(e)),
-		Ok(o) => o.into_eval_node().eval(env),
-	};
-	Some(res)
-}
-
-impl EvalNode for Eval<ArrayLiteral> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
+impl EvalNode for Eval<'_, ArrayLiteral> {
+	fn eval(self, env: &mut Env) -> ResultEval {
 		let mems = self
 			.node
 			.mems
-			.into_iter()
-			.map(|m| m.into_eval_node().eval(env))
-			.collect::<Result<VecDeque<Box<dyn Obj>>, EvalError>>()?;
-		Ok(Box::new(Array { mems }))
+			.iter()
+			.map(|m| eval_node(m, env))
+			.collect::<Result<VecDeque<Value>, Unwind>>()?;
+		Ok(Value::Array(Array::new(mems)))
 	}
 }
 
-impl EvalNode for Eval<BooleanLiteral> {
-	fn eval(self: Box<Self>, _env: &mut Env) -> ResultObj {
-		return Ok(Box::new(Boolean {
-			val: self.node.value,
-		}));
+impl EvalNode for Eval<'_, BooleanLiteral> {
+	fn eval(self, _env: &mut Env) -> ResultEval {
+		Ok(Value::Boolean(self.node.value))
 	}
 }
-impl EvalNode for Eval<LetStatement> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
+impl EvalNode for Eval<'_, LetStatement> {
+	fn eval(self, env: &mut Env) -> ResultEval {
 		let LetStatement {
 			token: _,
 			name,
 			value,
 		} = self.node;
-		let value = value.into_eval_node().eval(env)?;
-		env.set(name.value, value);
-		Ok(Box::new(Null {}))
+		let value = eval_node(value, env)?;
+		env.set(name.value.clone(), value);
+		Ok(Value::Null)
 	}
 }
-impl EvalNode for Eval<ReturnStatement> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
-		Ok(Box::new(ReturnValue {
-			val: self.node.value.into_eval_node().eval(env)?,
-		}))
+impl EvalNode for Eval<'_, AssignExpression> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let AssignExpression {
+			token,
+			name,
+			operator,
+			value,
+		} = self.node;
+		let current = match operator.strip_suffix('=').filter(|op| !op.is_empty()) {
+			Some(op) => {
+				let current = env
+					.get_assignable(&name.value)
+					.map_err(|e| e.located(name.token.span))?;
+				Some((op.to_string(), current))
+			}
+			None => None,
+		};
+		let value = eval_node(value.as_ref(), env)?;
+		let value = match current {
+			Some((op, current)) => {
+				eval_infix(&op, current, value).map_err(|e| e.located(token.span))?
+			}
+			None => value,
+		};
+		env.assign(&name.value, value.clone())
+			.map_err(|e| e.located(name.token.span))?;
+		Ok(value)
 	}
 }
-impl EvalNode for Eval<Identifier> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
-		env.get(self.node.value).cloned()
+impl EvalNode for Eval<'_, IndexAssignExpression> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let IndexAssignExpression {
+			token,
+			left,
+			index,
+			operator,
+			value,
+		} = self.node;
+		let left = eval_node(left.as_ref(), env)?;
+		let index = eval_node(index.as_ref(), env)?;
+		let value = eval_node(value.as_ref(), env)?;
+		Ok(eval_index_assign(operator, left, index, value).map_err(|e| e.located(token.span))?)
 	}
 }
-impl EvalNode for Eval<PrefixExpression> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
-		let PrefixExpression {
-			operator,
-			right,
+
+// shared with the vm, operator is = or a compound assignment such as +=
+pub fn eval_index_assign(operator: &str, left: Value, index: Value, value: Value) -> ResultObj {
+	let value = match operator.strip_suffix('=').filter(|op| !op.is_empty()) {
+		Some(op) => {
+			let current = eval_index(left.clone(), index.clone())?;
+			eval_infix(op, current, value)?
+		}
+		None => value,
+	};
+	set_index(&left, index, value.clone())?;
+	Ok(value)
+}
+
+impl EvalNode for Eval<'_, WhileStatement> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let WhileStatement {
 			token: _,
-		} = (*self).node;
-		let right = right.into_eval_node().eval(env)?;
-		match operator.as_str() {
-			"!" => bang_op(right),
-			"-" => minus_op(right),
-			_ => Ok(Box::new(Null {})),
+			condition,
+			body,
+		} = self.node;
+		loop {
+			if !eval_node(condition, env)?.is_truthy() {
+				break;
+			}
+			match eval_node(body, env) {
+				Ok(_) | Err(Unwind::Continue) => (),
+				Err(Unwind::Break) => break,
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(Value::Null)
+	}
+}
+
+impl EvalNode for Eval<'_, ForStatement> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let ForStatement {
+			token,
+			vars,
+			iterable,
+			body,
+		} = self.node;
+		let iterable = eval_node(iterable, env)?;
+		let iter = iterate(&iterable).map_err(|e| e.located(token.span))?;
+		while let Some(values) = iter.next(vars.len()) {
+			// a new scope each time round so closures made in the body keep their own values
+			let scope = &mut Env::new(Some(Box::new(env.clone())));
+			zip(vars, values).for_each(|(v, val)| scope.set(v.value.clone(), val));
+			match eval_node(body, scope) {
+				Ok(_) | Err(Unwind::Continue) => (),
+				Err(Unwind::Break) => break,
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(Value::Null)
+	}
+}
+
+pub fn iterate(obj: &Value) -> Result<Iter, EvalError> {
+	let source = match obj {
+		Value::Array(a) => IterSource::Array(a.mems.clone()),
+		Value::Hash(h) => IterSource::Pairs(h.pairs.borrow().values().cloned().collect()),
+		Value::String(s) => IterSource::Chars(s.chars().collect()),
+		Value::Range(start, end) => IterSource::Range(*start, *end),
+		_ => {
+			return Err(EvalError::UnexpectedNode(format!(
+				"Cannot iterate over {}",
+				obj.get_type().string()
+			)))
 		}
+	};
+	Ok(Iter::new(source))
+}
+
+impl EvalNode for Eval<'_, BreakStatement> {
+	fn eval(self, _env: &mut Env) -> ResultEval {
+		Err(Unwind::Break)
 	}
 }
-fn bang_op(right: Box<dyn Obj>) -> ResultObj {
-	match right.inspect_obj().as_str() {
-		"true" => Ok(Box::new(Boolean { val: false })),
-		"false" => Ok(Box::new(Boolean { val: true })),
-		"null" => Ok(Box::new(Boolean { val: true })),
-		_ => Ok(Box::new(Boolean { val: false })),
+
+impl EvalNode for Eval<'_, ContinueStatement> {
+	fn eval(self, _env: &mut Env) -> ResultEval {
+		Err(Unwind::Continue)
 	}
 }
-fn minus_op(right: Box<dyn Obj + 'static>) -> ResultObj {
-	let val = -right
-		.as_any()
-		.downcast_ref::<Integer>()
-		.ok_or(EvalError::UnexpectedNode(String::from(
-			"- Must be followed by an integer",
-		)))?
-		.val;
 
-	Ok(Box::new(Integer { val }))
+impl EvalNode for Eval<'_, ReturnStatement> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		Err(Unwind::Return(eval_node(&self.node.value, env)?))
+	}
+}
+impl EvalNode for Eval<'_, Identifier> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let Identifier { token, value } = self.node;
+		Ok(env.get(value).map_err(|e| e.located(token.span))?)
+	}
+}
+impl EvalNode for Eval<'_, PrefixExpression> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let PrefixExpression {
+			operator,
+			right,
+			token,
+		} = self.node;
+		let right = eval_node(right.as_ref(), env)?;
+		let result: ResultObj = match operator.as_str() {
+			"!" => Ok(bang_op(&right)),
+			"-" => minus_op(right),
+			_ => Ok(Value::Null),
+		};
+		Ok(result.map_err(|e| e.located(token.span))?)
+	}
+}
+pub fn bang_op(right: &Value) -> Value {
+	Value::Boolean(!right.is_truthy())
 }
-impl EvalNode for Eval<InfixExpression> {
-	fn eval(self: Box<Self>, env: &mut Env) -> ResultObj {
+pub fn minus_op(right: Value) -> ResultObj {
+	match right {
+		Value::Float(val) => Ok(Value::Float(-val)),
+		Value::Integer(val) if val != i64::MIN => Ok(Value::Integer(-val)),
+		_ => {
+			let val = to_big(&right).ok_or(EvalError::UnexpectedNode(String::from(
+				"- Must be followed by an integer or float",
+			)))?;
+			Ok(integer_obj(val.neg()))
+		}
+	}
+}
+impl EvalNode for Eval<'_, InfixExpression> {
+	fn eval(self, env: &mut Env) -> ResultEval {
 		let InfixExpression {
 			operator,
 			left,
 			right,
-			token: _,
-		} = (*self).node;
-		let left = left.into_eval_node().eval(env)?;
-		let right = right.into_eval_node().eval(env)?;
+			token,
+		} = self.node;
+		let left = eval_node(left.as_ref(), env)?;
+		let right = eval_node(right.as_ref(), env)?;
 
-		match (left.get_type(), right.get_type()) {
-			(ObjType::Integer, ObjType::Integer) => {
-				infix_eval_int(operator, left, right)
-			}
-			(ObjType::Boolean, ObjType::Boolean) => {
-				infix_eval_bool(operator, left, right)
-			}
-			(ObjType::String, ObjType::String) => infix_eval_str(operator, left, right),
-			_ => Err(EvalError::UnexpectedNode(String::from(format!(
-				"{0} {2} {1} :Infix operation undefined",
-				left.get_type().string(),
-				right.get_type().string(),
-				operator,
-			)))),
-		}
+		Ok(eval_infix(operator, left, right).map_err(|e| e.located(token.span))?)
 	}
 }
-fn infix_eval_str(
-	operator: String,
-	left: Box<dyn Obj>,
-	right: Box<dyn Obj>,
-) -> Result<Box<dyn Obj>, EvalError> {
-	let left = &left
-		.as_any()
-		.downcast_ref::<StringObj>()
-		.ok_or(EvalError::UnexpectedNode(format!(
-			"{0} must operate on an integer to the left",
+
+impl EvalNode for Eval<'_, LogicalExpression> {
+	fn eval(self, env: &mut Env) -> ResultEval {
+		let LogicalExpression {
+			token,
+			left,
 			operator,
-		)))?
-		.val;
-	let right = &right
-		.as_any()
-		.downcast_ref::<StringObj>()
-		.ok_or(EvalError::UnexpectedNode(format!(
-			"{0} must operate on an integer to the right",
+			right,
+		} = self.node;
+		let left = eval_node(left.as_ref(), env)?;
+		let decides = logical_decides(operator, &left);
+		match decides.map_err(|e| e.located(token.span))? {
+			true => Ok(left),
+			false => eval_node(right.as_ref(), env),
+		}
+	}
+}
+
+// whether the left operand of && or || is the result without looking at the right
+pub fn logical_decides(operator: &str, left: &Value) -> Result<bool, EvalError> {
+	let left = left.is_truthy();
+	match operator {
+		"&&" => Ok(!left),
+		"||" => Ok(left),
+		_ => Err(EvalError::UnexpectedNode(format!(
+			"{} is not a logical operator",
+			operator
+		))),
+	}
+}
+
+pub fn eval_infix(operator: &str, left: Value, right: Value) -> ResultObj {
+	match (&left, &right) {
+		(Value::Integer(_) | Value::BigInteger(_), Value::Integer(_) | Value::BigInteger(_)) => {
+			infix_eval_int(operator, &left, &right)
+		}
+		(
+			Value::Integer(_) | Value::BigInteger(_) | Value::Float(_),
+			Value::Integer(_) | Value::BigInteger(_) | Value::Float(_),
+		) => infix_eval_float(operator, &left, &right),
+		(Value::Boolean(l), Value::Boolean(r)) => infix_eval_bool(operator, *l, *r),
+		(Value::String(l), Value::String(r)) => infix_eval_str(operator, l, r),
+		_ if operator == "==" || operator == "!=" => {
+			let equal = objects_equal(&left, &right)?;
+			Ok(Value::Boolean(equal == (operator == "==")))
+		}
+		_ => Err(EvalError::UnexpectedNode(format!(
+			"{0} {2} {1} :Infix operation undefined",
+			left.get_type().string(),
+			right.get_type().string(),
 			operator,
-		)))?
-		.val;
+		))),
+	}
+}
+// arrays and hashes are equal when their members are, numbers compare by value and
+// any other values of different types are never equal
+fn objects_equal(left: &Value, right: &Value) -> Result<bool, EvalError> {
+	match (left, right) {
+		(Value::Array(l), Value::Array(r)) => arrays_equal(l, r),
+		(Value::Hash(l), Value::Hash(r)) => hashes_equal(l, r),
+		(Value::Null, Value::Null) => Ok(true),
+		(Value::String(l), Value::String(r)) => Ok(l == r),
+		(Value::Boolean(l), Value::Boolean(r)) => Ok(l == r),
+		(
+			Value::Integer(_) | Value::BigInteger(_) | Value::Float(_),
+			Value::Integer(_) | Value::BigInteger(_) | Value::Float(_),
+		) => {
+			let equal = eval_infix("==", left.clone(), right.clone())?;
+			Ok(matches!(equal, Value::Boolean(true)))
+		}
+		_ if left.get_type() == right.get_type() => Err(EvalError::UnexpectedNode(format!(
+			"Cannot compare {} values",
+			left.get_type().string()
+		))),
+		_ => Ok(false),
+	}
+}
 
-	match operator.as_str() {
-		"+" => Ok(Box::new(StringObj {
-			val: left.to_owned() + right.as_str(),
-		})),
+fn arrays_equal(left: &Array, right: &Array) -> Result<bool, EvalError> {
+	let (left, right) = (left.mems.borrow(), right.mems.borrow());
+	if left.len() != right.len() {
+		return Ok(false);
+	}
+	for (l, r) in zip(left.iter(), right.iter()) {
+		if !objects_equal(l, r)? {
+			return Ok(false);
+		}
+	}
+	Ok(true)
+}
+
+fn hashes_equal(left: &Hash, right: &Hash) -> Result<bool, EvalError> {
+	let (left, right) = (left.pairs.borrow(), right.pairs.borrow());
+	if left.len() != right.len() {
+		return Ok(false);
+	}
+	for (k, HashPair(_, l)) in left.iter() {
+		match right.get(k) {
+			Some(HashPair(_, r)) if objects_equal(l, r)? => (),
+			_ => return Ok(false),
+		}
+	}
+	Ok(true)
+}
+
+fn infix_eval_str(operator: &str, left: &str, right: &str) -> ResultObj {
+	match operator {
+		"+" => Ok(Value::String((left.to_owned() + right).into())),
+		"<" => Ok(Value::Boolean(left < right)),
+		">" => Ok(Value::Boolean(left > right)),
+		"<=" => Ok(Value::Boolean(left <= right)),
+		">=" => Ok(Value::Boolean(left >= right)),
+		"==" => Ok(Value::Boolean(left == right)),
+		"!=" => Ok(Value::Boolean(left != right)),
 		_ => Err(EvalError::Undefined(format!(
 			"String {} String Undefined",
 			operator
 		))),
 	}
 }
-fn infix_eval_bool(
-	operator: String,
-	left: Box<dyn Obj>,
-	right: Box<dyn Obj>,
-) -> Result<Box<dyn Obj>, EvalError> {
-	let left = left
-		.as_any()
-		.downcast_ref::<Boolean>()
-		.ok_or(EvalError::UnexpectedNode(format!(
-			"{0} must operate on an integer to the left",
-			operator,
-		)))?
-		.val;
-	let right = right
-		.as_any()
-		.downcast_ref::<Boolean>()
-		.ok_or(EvalError::UnexpectedNode(format!(
-			"{0} must operate on an integer to the right",
-			operator,
-		)))?
-		.val;
+fn infix_eval_bool(operator: &str, left: bool, right: bool) -> ResultObj {
+	match operator {
+		"==" => Ok(Value::Boolean(left == right)),
+		"!=" => Ok(Value::Boolean(left != right)),
+		_ => Err(EvalError::UnexpectedNode(String::from(
+			"operator is not recognised as an infix expression",
+		))),
+	}
+}
 
-	match operator.as_str() {
-		"==" => Ok(Box::new(Boolean { val: left == right })),
-		"!=" => Ok(Box::new(Boolean { val: left != right })),
+// an Integer operand is promoted to Float whenever the other operand is a Float
+fn to_float(obj: &Value) -> Option<f64> {
+	match obj {
+		Value::Float(val) => Some(*val),
+		_ => to_big(obj).map(|i| i.to_f64()),
+	}
+}
+
+fn to_big(obj: &Value) -> Option<BigInt> {
+	match obj {
+		Value::Integer(val) => Some(BigInt::from_i64(*val)),
+		Value::BigInteger(val) => Some(val.as_ref().clone()),
+		_ => None,
+	}
+}
+
+fn infix_eval_float(operator: &str, left: &Value, right: &Value) -> ResultObj {
+	let left = to_float(left).ok_or(EvalError::UnexpectedNode(format!(
+		"{0} must operate on a number to the left",
+		operator,
+	)))?;
+	let right = to_float(right).ok_or(EvalError::UnexpectedNode(format!(
+		"{0} must operate on a number to the right",
+		operator,
+	)))?;
+
+	match operator {
+		"+" => Ok(Value::Float(left + right)),
+		"-" => Ok(Value::Float(left - right)),
+		"*" => Ok(Value::Float(left * right)),
+		"/" => Ok(Value::Float(left / right)),
+		"%" => Ok(Value::Float(left % right)),
+		"<" => Ok(Value::Boolean(left < right)),
+		">" => Ok(Value::Boolean(left > right)),
+		"<=" => Ok(Value::Boolean(left <= right)),
+		">=" => Ok(Value::Boolean(left >= right)),
+		"==" => Ok(Value::Boolean(left == right)),
+		"!=" => Ok(Value::Boolean(left != right)),
 		_ => Err(EvalError::UnexpectedNode(String::from(
 			"operator is not recognised as an infix expression",
 		))),
 	}
 }
 
-fn infix_eval_int(
-	operator: String,
-	left: Box<dyn Obj + 'static>,
-	right: Box<dyn Obj + 'static>,
-) -> Result<Box<dyn Obj>, EvalError> {
-	let left = left
-		.as_any()
-		.downcast_ref::<Integer>()
-		.ok_or(EvalError::UnexpectedNode(format!(
-			"{0} must operate on an integer to the left",
-			operator,
-		)))?
-		.val;
-	let right = right
-		.as_any()
-		.downcast_ref::<Integer>()
-		.ok_or(EvalError::UnexpectedNode(format!(
-			"{0} must operate on an integer to the right",
-			operator,
-		)))?
-		.val;
-
-	match operator.as_str() {
-		"+" => Ok(Box::new(Integer { val: left + right })),
-		"-" => Ok(Box::new(Integer { val: left - right })),
-		"*" => Ok(Box::new(Integer { val: left * right })),
-		"/" => Ok(Box::new(Integer { val: left / right })),
-		"<" => Ok(Box::new(Boolean { val: left < right })),
-		">" => Ok(Box::new(Boolean { val: left > right })),
-		"==" => Ok(Box::new(Boolean { val: left == right })),
-		"!=" => Ok(Box::new(Boolean { val: left != right })),
+// stays on i64 while the result fits, overflow such as i64::MIN / -1 promotes to BigInt
+fn infix_eval_int(operator: &str, left: &Value, right: &Value) -> ResultObj {
+	if let (Value::Integer(l), Value::Integer(r)) = (left, right) {
+		let val = match operator {
+			"+" => l.checked_add(*r),
+			"-" => l.checked_sub(*r),
+			"*" => l.checked_mul(*r),
+			"/" => l.checked_div(*r),
+			"%" => l.checked_rem(*r),
+			_ => None,
+		};
+		if let Some(val) = val {
+			return Ok(Value::Integer(val));
+		}
+		let cmp = match operator {
+			"<" => Some(l < r),
+			">" => Some(l > r),
+			"<=" => Some(l <= r),
+			">=" => Some(l >= r),
+			"==" => Some(l == r),
+			"!=" => Some(l != r),
+			_ => None,
+		};
+		if let Some(val) = cmp {
+			return Ok(Value::Boolean(val));
+		}
+	}
+
+	let by_zero = || EvalError::DivisionByZero {
+		operator: operator.to_string(),
+		left: left.clone(),
+		right: right.clone(),
+	};
+	let l = to_big(left).ok_or(EvalError::UnexpectedNode(format!(
+		"{0} must operate on an integer to the left",
+		operator,
+	)))?;
+	let r = to_big(right).ok_or(EvalError::UnexpectedNode(format!(
+		"{0} must operate on an integer to the right",
+		operator,
+	)))?;
+
+	match operator {
+		"+" => Ok(integer_obj(l.add(&r))),
+		"-" => Ok(integer_obj(l.sub(&r))),
+		"*" => Ok(integer_obj(l.mul(&r))),
+		"/" => Ok(integer_obj(l.div(&r).ok_or_else(by_zero)?)),
+		"%" => Ok(integer_obj(l.rem(&r).ok_or_else(by_zero)?)),
+		"<" => Ok(Value::Boolean(l < r)),
+		">" => Ok(Value::Boolean(l > r)),
+		"<=" => Ok(Value::Boolean(l <= r)),
+		">=" => Ok(Value::Boolean(l >= r)),
+		"==" => Ok(Value::Boolean(l == r)),
+		"!=" => Ok(Value::Boolean(l != r)),
 		_ => Err(EvalError::UnexpectedNode(String::from(
 			"operator is not recognised as an infix expression",
 		))),
@@ -6,6 +6,10 @@ use crate::token::*;
 pub struct Lexer<I: Iterator<Item = char>> {
 	input: Peekable<I>,
 	ch: Option<char>,
+	line: usize,
+	column: usize,
+	offset: usize,
+	trivia: bool,
 }
 fn is_letter(ch: char) -> bool {
 	ch.is_alphabetic() || ch == '_'
@@ -17,6 +21,11 @@ fn lookup_ident(ident: &String) -> TokenType {
 		"if" => TokenType::If,
 		"else" => TokenType::Else,
 		"return" => TokenType::Return,
+		"while" => TokenType::While,
+		"break" => TokenType::Break,
+		"continue" => TokenType::Continue,
+		"for" => TokenType::For,
+		"in" => TokenType::In,
 		"true" => TokenType::True,
 		"false" => TokenType::False,
 		_ => TokenType::Ident,
@@ -27,10 +36,32 @@ impl<I: Iterator<Item = char>> Lexer<I> {
 	pub fn new(input: I) -> Lexer<I> {
 		let mut input = input.peekable();
 		let ch = input.next();
-		Lexer { input, ch }
+		Lexer {
+			input,
+			ch,
+			line: 1,
+			column: 1,
+			offset: 0,
+			trivia: false,
+		}
+	}
+
+	// emit comments as Comment tokens instead of skipping them
+	pub fn with_trivia(mut self) -> Self {
+		self.trivia = true;
+		self
 	}
 
 	fn read_char(&mut self) {
+		match self.ch {
+			Some('\n') => {
+				self.line += 1;
+				self.column = 1;
+			}
+			Some(_) => self.column += 1,
+			None => (),
+		}
+		self.offset += self.ch.map_or(0, |c| c.len_utf8());
 		self.ch = self.input.next();
 	}
 
@@ -47,7 +78,7 @@ impl<I: Iterator<Item = char>> Lexer<I> {
 		ident.into_iter().collect()
 	}
 
-	fn read_number(&mut self) -> String {
+	fn read_digits(&mut self) -> String {
 		let mut int = vec![];
 		while self.ch.is_some() && self.ch.unwrap().is_ascii_digit() {
 			int.push(self.ch.unwrap());
@@ -56,7 +87,51 @@ impl<I: Iterator<Item = char>> Lexer<I> {
 		int.into_iter().collect()
 	}
 
-	fn read_single_char_token(&mut self) -> Option<Token> {
+	// a fraction needs a digit after the dot and an exponent needs digits after the optional sign
+	fn read_number(&mut self) -> (TokenType, String) {
+		let mut num = self.read_digits();
+		let mut token_type = TokenType::Int;
+		if self.ch == Some('.') && self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
+			num.push('.');
+			self.read_char();
+			num += self.read_digits().as_str();
+			token_type = TokenType::Float;
+		}
+		if matches!(self.ch, Some('e' | 'E'))
+			&& matches!(self.peek_char(), Some('+' | '-' | '0'..='9'))
+		{
+			num.push(self.ch.unwrap());
+			self.read_char();
+			if let Some(sign) = self.ch.filter(|c| *c == '+' || *c == '-') {
+				num.push(sign);
+				self.read_char();
+			}
+			let exponent = self.read_digits();
+			if exponent.is_empty() {
+				return (
+					TokenType::Error,
+					format!("Invalid float literal {}, the exponent has no digits", num),
+				);
+			}
+			num += exponent.as_str();
+			token_type = TokenType::Float;
+		}
+		(token_type, num)
+	}
+
+	// an arithmetic operator directly followed by = is its compound assignment
+	fn read_operator(&mut self, plain: TokenType, assign: TokenType) -> (TokenType, String) {
+		let ch = self.ch.unwrap();
+		match self.peek_char() {
+			Some('=') => {
+				self.read_char();
+				(assign, format!("{}=", ch))
+			}
+			_ => (plain, ch.to_string()),
+		}
+	}
+
+	fn read_single_char_token(&mut self) -> Option<(TokenType, String)> {
 		let ch = self.ch.unwrap();
 		match ch {
 			'=' => {
@@ -64,100 +139,217 @@ impl<I: Iterator<Item = char>> Lexer<I> {
 				if ch_next.is_some() && ch_next.unwrap() == '=' {
 					self.read_char();
 					let ch_next = self.ch.unwrap();
-					return Some(token!(
-						TokenType::Eq,
-						format!("{}{}", ch, ch_next)
-					));
+					return Some((TokenType::Eq, format!("{}{}", ch, ch_next)));
 				}
-				Some(token!(TokenType::Assign, ch.to_string()))
+				Some((TokenType::Assign, ch.to_string()))
 			}
-			'+' => Some(token!(TokenType::Plus, ch.to_string())),
-			'-' => Some(token!(TokenType::Minus, ch.to_string())),
+			'+' => Some(self.read_operator(TokenType::Plus, TokenType::PlusAssign)),
+			'-' => Some(self.read_operator(TokenType::Minus, TokenType::MinusAssign)),
 			'!' => {
 				let ch_next = self.peek_char();
 				if ch_next.is_some() && ch_next.unwrap() == '=' {
 					self.read_char();
 					let ch_next = self.ch.unwrap();
-					return Some(token!(
-						TokenType::Noteq,
-						format!("{}{}", ch, ch_next)
-					));
+					return Some((TokenType::Noteq, format!("{}{}", ch, ch_next)));
 				}
-				Some(token!(TokenType::Bang, ch.to_string()))
+				Some((TokenType::Bang, ch.to_string()))
+			}
+			'/' => Some(self.read_operator(TokenType::Slash, TokenType::SlashAssign)),
+			'*' => Some(self.read_operator(TokenType::Asterisk, TokenType::AsteriskAssign)),
+			'%' => Some(self.read_operator(TokenType::Percent, TokenType::PercentAssign)),
+			'&' if self.peek_char() == Some('&') => {
+				self.read_char();
+				Some((TokenType::And, String::from("&&")))
+			}
+			'|' if self.peek_char() == Some('|') => {
+				self.read_char();
+				Some((TokenType::Or, String::from("||")))
 			}
-			'/' => Some(token!(TokenType::Slash, ch.to_string())),
-			'*' => Some(token!(TokenType::Asterisk, ch.to_string())),
-			'<' => Some(token!(TokenType::Lt, ch.to_string())),
-			'>' => Some(token!(TokenType::Gt, ch.to_string())),
-			';' => Some(token!(TokenType::Semicolon, ch.to_string())),
-			'(' => Some(token!(TokenType::Lparen, ch.to_string())),
-			')' => Some(token!(TokenType::Rparen, ch.to_string())),
-			',' => Some(token!(TokenType::Comma, ch.to_string())),
-			'{' => Some(token!(TokenType::Lbrace, ch.to_string())),
-			'}' => Some(token!(TokenType::Rbrace, ch.to_string())),
-			'[' => Some(token!(TokenType::Lbracket, ch.to_string())),
-			']' => Some(token!(TokenType::Rbracket, ch.to_string())),
-			'"' => Some(token!(
-				TokenType::String,
-				self.read_string(&mut String::new())
-			)),
-			':' => Some(token!(TokenType::Colon, ch.to_string())),
+			'<' => Some(self.read_operator(TokenType::Lt, TokenType::LtEq)),
+			'>' => Some(self.read_operator(TokenType::Gt, TokenType::GtEq)),
+			';' => Some((TokenType::Semicolon, ch.to_string())),
+			'(' => Some((TokenType::Lparen, ch.to_string())),
+			')' => Some((TokenType::Rparen, ch.to_string())),
+			',' => Some((TokenType::Comma, ch.to_string())),
+			'{' => Some((TokenType::Lbrace, ch.to_string())),
+			'}' => Some((TokenType::Rbrace, ch.to_string())),
+			'[' => Some((TokenType::Lbracket, ch.to_string())),
+			']' => Some((TokenType::Rbracket, ch.to_string())),
+			':' => Some((TokenType::Colon, ch.to_string())),
 			_ => None,
 		}
 	}
-	fn read_string(&mut self, acc: &mut String) -> String {
+	fn span_from(&self, start: usize, line: usize, column: usize) -> Span {
+		Span {
+			start,
+			end: self.offset,
+			line,
+			column,
+		}
+	}
+
+	// on a bad escape the rest of the string is still consumed so lexing resumes after it
+	fn read_string(&mut self) -> Result<String, (String, Span)> {
+		let (start, line, column) = (self.offset, self.line, self.column);
+		let mut val = String::new();
+		let mut error = None;
 		self.read_char();
-		let ch = self.ch.unwrap();
-		if ch == '"' {
-			return (*acc).to_string();
+		loop {
+			match self.ch {
+				None => {
+					let span = self.span_from(start, line, column);
+					return Err((String::from("Unterminated string"), span));
+				}
+				Some('"') => {
+					self.read_char();
+					break;
+				}
+				Some('\\') => match self.read_escape() {
+					Ok(ch) => val.push(ch),
+					Err(e) => error = error.or(Some(e)),
+				},
+				Some(ch) => {
+					val.push(ch);
+					self.read_char();
+				}
+			}
+		}
+		match error {
+			Some(e) => Err(e),
+			None => Ok(val),
 		}
-		acc.push(ch);
-		self.read_string(acc)
 	}
 
-	fn skip_whitespace(&mut self) {
-		while self.ch == Some(' ')
-			|| self.ch == Some('\t') || self.ch == Some('\n')
-			|| self.ch == Some('\r')
-		{
+	fn read_escape(&mut self) -> Result<char, (String, Span)> {
+		let (start, line, column) = (self.offset, self.line, self.column);
+		self.read_char();
+		let escape = self.ch;
+		if escape.is_some() {
 			self.read_char();
 		}
+		let ch = match escape {
+			Some('n') => Some('\n'),
+			Some('t') => Some('\t'),
+			Some('r') => Some('\r'),
+			Some('\\') => Some('\\'),
+			Some('"') => Some('"'),
+			Some('u') => self.read_unicode_escape(),
+			_ => None,
+		};
+		ch.ok_or_else(|| {
+			let span = self.span_from(start, line, column);
+			let text = escape.map_or(String::new(), |c| c.to_string());
+			(format!("Invalid escape sequence \\{}", text), span)
+		})
 	}
-}
 
-impl<I: Iterator<Item = char>> Iterator for Lexer<I> {
-	type Item = Token;
+	fn read_unicode_escape(&mut self) -> Option<char> {
+		if self.ch != Some('{') {
+			return None;
+		}
+		self.read_char();
+		let mut digits = String::new();
+		while let Some(ch) = self.ch.filter(|c| c.is_ascii_hexdigit()) {
+			digits.push(ch);
+			self.read_char();
+		}
+		if self.ch != Some('}') {
+			return None;
+		}
+		self.read_char();
+		if digits.is_empty() || digits.len() > 6 {
+			return None;
+		}
+		char::from_u32(u32::from_str_radix(&digits, 16).ok()?)
+	}
 
-	fn next(&mut self) -> Option<Self::Item> {
-		let tok;
+	// returns false when a block comment is still open at the end of the input
+	fn read_comment(&mut self, text: &mut String) -> bool {
+		let block = self.peek_char() == Some('*');
+		text.push(self.ch.unwrap());
+		self.read_char();
+		text.push(self.ch.unwrap());
+		self.read_char();
+		if !block {
+			while let Some(ch) = self.ch.filter(|c| *c != '\n') {
+				text.push(ch);
+				self.read_char();
+			}
+			return true;
+		}
+
+		let mut depth = 1;
+		while depth > 0 {
+			let ch = match self.ch {
+				Some(ch) => ch,
+				None => return false,
+			};
+			match (ch, self.peek_char()) {
+				('/', Some('*')) | ('*', Some('/')) => {
+					depth += if ch == '/' { 1 } else { -1 };
+					text.push(ch);
+					self.read_char();
+				}
+				_ => (),
+			}
+			text.push(self.ch.unwrap());
+			self.read_char();
+		}
+		true
+	}
 
+	fn read_token(&mut self) -> Option<Token> {
 		self.skip_whitespace();
+		let (start, line, column) = (self.offset, self.line, self.column);
+		let ch = self.ch?;
 
-		if self.ch.is_none() {
-			tok = None;
-			self.read_char();
+		let (token_type, literal) = if ch == '/' && matches!(self.peek_char(), Some('/' | '*')) {
+			let mut text = String::new();
+			match self.read_comment(&mut text) {
+				true => (TokenType::Comment, text),
+				false => (TokenType::Error, String::from("Unterminated block comment")),
+			}
+		} else if ch == '"' {
+			match self.read_string() {
+				Ok(val) => (TokenType::String, val),
+				Err((msg, span)) => return Some(token!(TokenType::Error, msg, span)),
+			}
 		} else if let Some(token) = self.read_single_char_token() {
-			tok = Some(token);
 			self.read_char();
-		} else if is_letter(self.ch.unwrap()) {
+			token
+		} else if is_letter(ch) {
 			let ident = self.read_identifier();
-			tok = Some(Token {
-				token_type: lookup_ident(&ident),
-				literal: ident,
-			});
-		} else if self.ch.unwrap().is_ascii_digit() {
-			tok = Some(Token {
-				token_type: TokenType::Int,
-				literal: self.read_number(),
-			});
+			(lookup_ident(&ident), ident)
+		} else if ch.is_ascii_digit() {
+			self.read_number()
 		} else {
-			tok = Some(Token {
-				token_type: TokenType::Illegal,
-				literal: self.ch.unwrap().to_string(),
-			});
 			self.read_char();
+			(TokenType::Illegal, ch.to_string())
 		};
 
-		tok
+		let span = self.span_from(start, line, column);
+		Some(token!(token_type, literal, span))
+	}
+
+	fn skip_whitespace(&mut self) {
+		while self.ch == Some(' ')
+			|| self.ch == Some('\t') || self.ch == Some('\n')
+			|| self.ch == Some('\r')
+		{
+			self.read_char();
+		}
+	}
+}
+
+impl<I: Iterator<Item = char>> Iterator for Lexer<I> {
+	type Item = Token;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let token = self.read_token()?;
+			if self.trivia || token.token_type != TokenType::Comment {
+				return Some(token);
+			}
+		}
 	}
 }
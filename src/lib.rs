@@ -0,0 +1,13 @@
+pub mod ast;
+pub mod code;
+pub mod compiler;
+pub mod diagnostics;
+pub mod disassembler;
+pub mod eval;
+pub mod lexer;
+pub mod module;
+pub mod object;
+pub mod parser;
+pub mod repl;
+pub mod token;
+pub mod vm;
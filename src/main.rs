@@ -1,11 +1,159 @@
-mod ast;
-mod eval;
-mod lexer;
-mod object;
-mod parser;
-mod repl;
-mod token;
+use monkey_compiler::compiler::{Bytecode, Compiler};
+use monkey_compiler::diagnostics::{self, Diagnostic};
+use monkey_compiler::lexer::Lexer;
+use monkey_compiler::module::{self, DebugInfo, Module};
+use monkey_compiler::object::Value;
+use monkey_compiler::parser::Parser;
+use monkey_compiler::vm::Vm;
+use monkey_compiler::{disassembler, repl};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
 
 fn main() {
-	repl::start();
+	let args: Vec<String> = env::args().collect();
+	match args.get(1).map(|a| a.as_str()) {
+		None => repl::start(),
+		Some("compile") => compile(&args[2..]),
+		Some("run") => run(&args[2..]),
+		Some("disasm") => disasm(&args[2..]),
+		Some("tokens") => tokens(&args[2..]),
+		Some(cmd) => exit_with(format!("Unknown command {}", cmd)),
+	}
+}
+
+fn compile(args: &[String]) {
+	let usage = "Usage: compile <file> [-o <out.mkc>] [--no-debug]";
+	let mut path = None;
+	let mut out = None;
+	let mut debug = true;
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-o" => {
+				let o = args
+					.next()
+					.unwrap_or_else(|| exit_with(String::from(usage)));
+				out = Some(o.clone());
+			}
+			"--no-debug" => debug = false,
+			_ if path.is_none() => path = Some(arg.clone()),
+			_ => exit_with(String::from(usage)),
+		}
+	}
+	let path = path.unwrap_or_else(|| exit_with(String::from(usage)));
+	let out: String = out.unwrap_or_else(|| {
+		Path::new(&path)
+			.with_extension("mkc")
+			.to_string_lossy()
+			.to_string()
+	});
+
+	let module = compile_module(&path, read_source(&path), debug);
+	let bytes = module::encode(&module)
+		.unwrap_or_else(|e| exit_with(format!("{}: {}", e.get_err_type(), e.get_err_msg())));
+	fs::write(&out, bytes).unwrap_or_else(|e| exit_with(format!("Could not write {}: {}", out, e)));
+}
+
+fn run(args: &[String]) {
+	let path = args
+		.first()
+		.unwrap_or_else(|| exit_with(String::from("Usage: run <file>")));
+	let Module { bytecode, debug } = load_module(path);
+	let mut vm = Vm::new(bytecode);
+	match vm.run() {
+		Ok(Value::Null) => (),
+		Ok(o) => println!("{}", o.inspect_obj()),
+		Err(e) => {
+			let diagnostic = Diagnostic::from(&e);
+			let report = match debug {
+				Some(DebugInfo {
+					source_name,
+					source,
+				}) => {
+					let span = diagnostic
+						.span
+						.or_else(|| diagnostics::line_span(&source, vm.current_line()?));
+					diagnostic.with_span(span).render(&source_name, &source)
+				}
+				None => diagnostic.render(path, ""),
+			};
+			exit_with(report.trim_end().to_string())
+		}
+	}
+}
+
+fn disasm(args: &[String]) {
+	let path = args
+		.first()
+		.unwrap_or_else(|| exit_with(String::from("Usage: disasm <file>")));
+	print!("{}", disassembler::disassemble(&load_module(path)));
+}
+
+fn tokens(args: &[String]) {
+	let usage = "Usage: tokens <file> [--trivia]";
+	let (path, trivia) = match args {
+		[path] => (path, false),
+		[path, flag] if flag == "--trivia" => (path, true),
+		_ => exit_with(String::from(usage)),
+	};
+	let source = read_source(path);
+	let lexer = Lexer::new(source.chars());
+	let lexer = match trivia {
+		true => lexer.with_trivia(),
+		false => lexer,
+	};
+	lexer.for_each(|t| {
+		println!(
+			"{}:{} {} {:?}",
+			t.span.line,
+			t.span.column,
+			t.token_type.get_name(),
+			t.literal
+		)
+	});
+}
+
+fn load_module(path: &String) -> Module {
+	let bytes =
+		fs::read(path).unwrap_or_else(|e| exit_with(format!("Could not read {}: {}", path, e)));
+	match module::is_module(&bytes) {
+		true => module::decode(&bytes)
+			.unwrap_or_else(|e| exit_with(format!("{}: {}", e.get_err_type(), e.get_err_msg()))),
+		false => compile_module(path, String::from_utf8_lossy(&bytes).to_string(), true),
+	}
+}
+
+fn compile_module(path: &str, source: String, debug: bool) -> Module {
+	Module {
+		bytecode: compile_source(path, &source),
+		debug: debug.then(|| DebugInfo {
+			source_name: path.to_string(),
+			source,
+		}),
+	}
+}
+
+fn read_source(path: &String) -> String {
+	fs::read_to_string(path)
+		.unwrap_or_else(|e| exit_with(format!("Could not read {}: {}", path, e)))
+}
+
+fn compile_source(path: &str, source: &str) -> Bytecode {
+	let program = Parser::new(Lexer::new(source.chars())).parse_program();
+	let mut compiler = Compiler::new();
+	if let Err(errors) = compiler.compile(program) {
+		let reports = errors
+			.iter()
+			.map(|e| Diagnostic::from(e).render(path, source))
+			.collect::<Vec<String>>();
+		exit_with(reports.join("\n").trim_end().to_string());
+	}
+	compiler.bytecode()
+}
+
+fn exit_with(msg: String) -> ! {
+	eprintln!("{}", msg);
+	process::exit(1)
 }
@@ -0,0 +1,416 @@
+use crate::code::{read_operands, Instructions, LineTable, Opcode, ASSIGN_OPERATORS};
+use crate::compiler::Bytecode;
+use crate::eval::builtins::BUILTINS;
+use crate::object::*;
+use std::rc::Rc;
+
+pub const MAGIC: &[u8; 4] = b"MKC\0";
+pub const FORMAT_VERSION: u16 = 7;
+
+const FLAG_DEBUG_INFO: u8 = 1;
+
+const TAG_INTEGER: u8 = 0;
+const TAG_STRING: u8 = 1;
+const TAG_FUNCTION: u8 = 2;
+const TAG_FLOAT: u8 = 3;
+
+const CAPTURE_LOCAL: u8 = 0;
+const CAPTURE_FREE: u8 = 1;
+
+#[derive(Debug)]
+pub enum ModuleError {
+	BadMagic(String),
+	UnsupportedVersion(String),
+	Corrupt(String),
+	Unsupported(String),
+}
+
+impl ModuleError {
+	pub fn get_err_type(&self) -> String {
+		match self {
+			ModuleError::BadMagic(_) => String::from("BadMagic"),
+			ModuleError::UnsupportedVersion(_) => String::from("UnsupportedVersion"),
+			ModuleError::Corrupt(_) => String::from("Corrupt"),
+			ModuleError::Unsupported(_) => String::from("Unsupported"),
+		}
+	}
+	pub fn get_err_msg(&self) -> String {
+		match self {
+			ModuleError::BadMagic(m) => m.to_string(),
+			ModuleError::UnsupportedVersion(m) => m.to_string(),
+			ModuleError::Corrupt(m) => m.to_string(),
+			ModuleError::Unsupported(m) => m.to_string(),
+		}
+	}
+}
+
+pub struct DebugInfo {
+	pub source_name: String,
+	pub source: String,
+}
+
+pub struct Module {
+	pub bytecode: Bytecode,
+	pub debug: Option<DebugInfo>,
+}
+
+pub fn is_module(bytes: &[u8]) -> bool {
+	bytes.starts_with(MAGIC)
+}
+
+pub fn encode(module: &Module) -> Result<Vec<u8>, ModuleError> {
+	let with_debug = module.debug.is_some();
+	let mut body = Vec::new();
+	write_u32(&mut body, module.bytecode.constants.len())?;
+	module
+		.bytecode
+		.constants
+		.iter()
+		.try_for_each(|c| write_constant(&mut body, c, with_debug))?;
+	write_u16(&mut body, module.bytecode.num_locals)?;
+	write_bytes(&mut body, &module.bytecode.instructions.0)?;
+	if let Some(debug) = &module.debug {
+		write_bytes(&mut body, debug.source_name.as_bytes())?;
+		write_bytes(&mut body, debug.source.as_bytes())?;
+		write_lines(&mut body, &module.bytecode.lines)?;
+	}
+
+	let flags = match module.debug {
+		Some(_) => FLAG_DEBUG_INFO,
+		None => 0,
+	};
+	let mut out = Vec::with_capacity(body.len() + 11);
+	out.extend_from_slice(MAGIC);
+	out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+	out.push(flags);
+	out.extend_from_slice(&checksum(&body).to_le_bytes());
+	out.extend(body);
+	Ok(out)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Module, ModuleError> {
+	if !is_module(bytes) {
+		return Err(ModuleError::BadMagic(String::from(
+			"Not a compiled monkey module",
+		)));
+	}
+	let mut r = Reader {
+		bytes,
+		pos: MAGIC.len(),
+	};
+	let version = r.read_u16()?;
+	if version != FORMAT_VERSION {
+		return Err(ModuleError::UnsupportedVersion(format!(
+			"Module format version {} is not supported, expected version {}",
+			version, FORMAT_VERSION
+		)));
+	}
+	let flags = r.read_u8()?;
+	if flags & !FLAG_DEBUG_INFO != 0 {
+		return Err(ModuleError::Corrupt(format!(
+			"Unknown module flags {:#04x}",
+			flags
+		)));
+	}
+	let expected = r.read_u32()?;
+	if checksum(&bytes[r.pos..]) != expected {
+		return Err(ModuleError::Corrupt(String::from(
+			"Module checksum mismatch",
+		)));
+	}
+
+	let with_debug = flags & FLAG_DEBUG_INFO != 0;
+	let num_constants = r.read_u32()? as usize;
+	let constants = (0..num_constants)
+		.map(|_| r.read_constant(with_debug))
+		.collect::<Result<Vec<_>, _>>()?;
+	let num_locals = r.read_u16()? as usize;
+	let instructions = Instructions(r.read_bytes()?.to_vec());
+	let (debug, lines) = match with_debug {
+		false => (None, LineTable::new()),
+		true => {
+			let debug = DebugInfo {
+				source_name: r.read_string()?,
+				source: r.read_string()?,
+			};
+			(Some(debug), r.read_lines()?)
+		}
+	};
+	if r.pos != bytes.len() {
+		return Err(ModuleError::Corrupt(String::from(
+			"Unexpected trailing bytes after module",
+		)));
+	}
+
+	validate_instructions(&instructions, &constants, num_locals, 0)?;
+	constants.iter().try_for_each(|c| match c {
+		Value::CompiledFunction(f) => {
+			validate_instructions(&f.instructions, &constants, f.num_locals, f.captures.len())
+		}
+		_ => Ok(()),
+	})?;
+
+	Ok(Module {
+		bytecode: Bytecode {
+			instructions,
+			lines,
+			constants,
+			num_locals,
+		},
+		debug,
+	})
+}
+
+fn write_constant(
+	out: &mut Vec<u8>,
+	constant: &Value,
+	with_debug: bool,
+) -> Result<(), ModuleError> {
+	match constant {
+		Value::Integer(val) => {
+			out.push(TAG_INTEGER);
+			out.extend_from_slice(&val.to_le_bytes());
+		}
+		Value::Float(val) => {
+			out.push(TAG_FLOAT);
+			out.extend_from_slice(&val.to_le_bytes());
+		}
+		Value::String(val) => {
+			out.push(TAG_STRING);
+			write_bytes(out, val.as_bytes())?;
+		}
+		Value::CompiledFunction(f) => {
+			out.push(TAG_FUNCTION);
+			write_u16(out, f.num_params)?;
+			write_u16(out, f.num_locals)?;
+			write_u16(out, f.captures.len())?;
+			f.captures.iter().try_for_each(|c| {
+				let (kind, index) = match c {
+					Capture::Local(i) => (CAPTURE_LOCAL, *i),
+					Capture::Free(i) => (CAPTURE_FREE, *i),
+				};
+				out.push(kind);
+				write_u16(out, index)
+			})?;
+			write_bytes(out, &f.instructions.0)?;
+			if with_debug {
+				write_lines(out, &f.lines)?;
+			}
+		}
+		_ => {
+			return Err(ModuleError::Unsupported(format!(
+				"{} constants cannot be written to a module",
+				constant.get_type().string()
+			)))
+		}
+	}
+	Ok(())
+}
+
+fn write_u16(out: &mut Vec<u8>, val: usize) -> Result<(), ModuleError> {
+	let val = u16::try_from(val)
+		.map_err(|_| ModuleError::Unsupported(format!("{} does not fit in 2 bytes", val)))?;
+	out.extend_from_slice(&val.to_le_bytes());
+	Ok(())
+}
+
+fn write_u32(out: &mut Vec<u8>, val: usize) -> Result<(), ModuleError> {
+	let val = u32::try_from(val)
+		.map_err(|_| ModuleError::Unsupported(format!("{} does not fit in 4 bytes", val)))?;
+	out.extend_from_slice(&val.to_le_bytes());
+	Ok(())
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), ModuleError> {
+	write_u32(out, bytes.len())?;
+	out.extend_from_slice(bytes);
+	Ok(())
+}
+
+fn write_lines(out: &mut Vec<u8>, lines: &LineTable) -> Result<(), ModuleError> {
+	write_u32(out, lines.len())?;
+	lines.iter().try_for_each(|(pos, line)| {
+		write_u32(out, *pos)?;
+		write_u32(out, *line)
+	})
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+	bytes
+		.iter()
+		.fold(0x811c9dc5, |h, b| (h ^ *b as u32).wrapping_mul(0x01000193))
+}
+
+struct Reader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, n: usize) -> Result<&'a [u8], ModuleError> {
+		let bytes = self
+			.bytes
+			.get(self.pos..self.pos + n)
+			.ok_or(ModuleError::Corrupt(String::from(
+				"Unexpected end of module",
+			)))?;
+		self.pos += n;
+		Ok(bytes)
+	}
+
+	fn read_u8(&mut self) -> Result<u8, ModuleError> {
+		Ok(self.take(1)?[0])
+	}
+
+	fn read_u16(&mut self) -> Result<u16, ModuleError> {
+		let b = self.take(2)?;
+		Ok(u16::from_le_bytes([b[0], b[1]]))
+	}
+
+	fn read_u32(&mut self) -> Result<u32, ModuleError> {
+		let b = self.take(4)?;
+		Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+	}
+
+	fn read_i64(&mut self) -> Result<i64, ModuleError> {
+		let mut b = [0; 8];
+		b.copy_from_slice(self.take(8)?);
+		Ok(i64::from_le_bytes(b))
+	}
+
+	fn read_f64(&mut self) -> Result<f64, ModuleError> {
+		let mut b = [0; 8];
+		b.copy_from_slice(self.take(8)?);
+		Ok(f64::from_le_bytes(b))
+	}
+
+	fn read_bytes(&mut self) -> Result<&'a [u8], ModuleError> {
+		let len = self.read_u32()? as usize;
+		self.take(len)
+	}
+
+	fn read_string(&mut self) -> Result<String, ModuleError> {
+		String::from_utf8(self.read_bytes()?.to_vec())
+			.map_err(|_| ModuleError::Corrupt(String::from("String is not valid utf-8")))
+	}
+
+	fn read_lines(&mut self) -> Result<LineTable, ModuleError> {
+		let len = self.read_u32()? as usize;
+		(0..len)
+			.map(|_| Ok((self.read_u32()? as usize, self.read_u32()? as usize)))
+			.collect()
+	}
+
+	fn read_capture(&mut self) -> Result<Capture, ModuleError> {
+		let kind = self.read_u8()?;
+		let index = self.read_u16()? as usize;
+		match kind {
+			CAPTURE_LOCAL => Ok(Capture::Local(index)),
+			CAPTURE_FREE => Ok(Capture::Free(index)),
+			kind => Err(ModuleError::Corrupt(format!(
+				"Unknown capture kind {}",
+				kind
+			))),
+		}
+	}
+
+	fn read_constant(&mut self, with_debug: bool) -> Result<Value, ModuleError> {
+		match self.read_u8()? {
+			TAG_INTEGER => Ok(Value::Integer(self.read_i64()?)),
+			TAG_FLOAT => Ok(Value::Float(self.read_f64()?)),
+			TAG_STRING => Ok(Value::String(self.read_string()?.into())),
+			TAG_FUNCTION => {
+				let num_params = self.read_u16()? as usize;
+				let num_locals = self.read_u16()? as usize;
+				if num_params > num_locals {
+					return Err(ModuleError::Corrupt(String::from(
+						"Function has more params than locals",
+					)));
+				}
+				let num_captures = self.read_u16()? as usize;
+				let captures = (0..num_captures)
+					.map(|_| self.read_capture())
+					.collect::<Result<Vec<_>, _>>()?;
+				let instructions = Instructions(self.read_bytes()?.to_vec());
+				let lines = match with_debug {
+					true => self.read_lines()?,
+					false => LineTable::new(),
+				};
+				Ok(Value::CompiledFunction(Rc::new(CompiledFunction {
+					instructions,
+					lines,
+					num_locals,
+					num_params,
+					captures,
+				})))
+			}
+			tag => Err(ModuleError::Corrupt(format!(
+				"Unknown constant tag {}",
+				tag
+			))),
+		}
+	}
+}
+
+// the main program has no free variables
+fn validate_instructions(
+	ins: &Instructions,
+	constants: &[Value],
+	num_locals: usize,
+	num_free: usize,
+) -> Result<(), ModuleError> {
+	let ins = &ins.0;
+	let mut i = 0;
+	while i < ins.len() {
+		let op = Opcode::from_byte(ins[i]).ok_or(ModuleError::Corrupt(format!(
+			"Unknown opcode {} at {}",
+			ins[i], i
+		)))?;
+		let def = op.definition();
+		let width: usize = def.operand_widths.iter().sum();
+		if i + 1 + width > ins.len() {
+			return Err(ModuleError::Corrupt(format!(
+				"{} at {} is missing operands",
+				def.name, i
+			)));
+		}
+		let (operands, read) = read_operands(&def, &ins[i + 1..]);
+		let in_range = match (op, operands.first()) {
+			(Opcode::Constant, Some(c)) => *c < constants.len(),
+			(Opcode::Closure, Some(c)) => match constants.get(*c) {
+				Some(Value::CompiledFunction(f)) => {
+					operands.get(1) == Some(&f.captures.len())
+						&& f.captures.iter().all(|c| match c {
+							Capture::Local(l) => *l < num_locals,
+							Capture::Free(i) => *i < num_free,
+						})
+				}
+				_ => false,
+			},
+			(
+				Opcode::Jump
+				| Opcode::JumpNotTruthy
+				| Opcode::JumpNotTruthyOrPop
+				| Opcode::JumpTruthyOrPop
+				| Opcode::LoopJump,
+				Some(t),
+			) => *t <= ins.len(),
+			(Opcode::IterNext, Some(t)) => {
+				*t <= ins.len() && matches!(operands.get(1), Some(1 | 2))
+			}
+			(Opcode::GetBuiltin, Some(b)) => *b < BUILTINS.len(),
+			(Opcode::SetIndex, Some(o)) => *o < ASSIGN_OPERATORS.len(),
+			(Opcode::GetLocal | Opcode::SetLocal | Opcode::FreshLocal, Some(l)) => *l < num_locals,
+			(Opcode::GetFree | Opcode::SetFree, Some(i)) => *i < num_free,
+			_ => true,
+		};
+		if !in_range {
+			return Err(ModuleError::Corrupt(format!(
+				"{} at {} has an out of range operand",
+				def.name, i
+			)));
+		}
+		i += 1 + read;
+	}
+	Ok(())
+}
@@ -0,0 +1,359 @@
+use std::cmp::Ordering;
+
+// sign and magnitude, the magnitude is little endian base 2^32 limbs with no
+// trailing zero limbs so every value has exactly one representation
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BigInt {
+	neg: bool,
+	mag: Vec<u32>,
+}
+
+impl BigInt {
+	pub fn from_i64(val: i64) -> Self {
+		let mut n = val.unsigned_abs();
+		let mut mag = Vec::new();
+		while n > 0 {
+			mag.push(n as u32);
+			n >>= 32;
+		}
+		BigInt { neg: val < 0, mag }
+	}
+
+	fn from_parts(neg: bool, mag: Vec<u32>) -> Self {
+		let mag = trim(mag);
+		BigInt {
+			neg: neg && !mag.is_empty(),
+			mag,
+		}
+	}
+
+	pub fn to_i64(&self) -> Option<i64> {
+		if self.mag.len() > 2 {
+			return None;
+		}
+		let n = self
+			.mag
+			.iter()
+			.rev()
+			.fold(0u64, |acc, l| (acc << 32) | *l as u64);
+		match self.neg {
+			false => i64::try_from(n).ok(),
+			true if n <= i64::MIN.unsigned_abs() => Some((n as i64).wrapping_neg()),
+			true => None,
+		}
+	}
+
+	pub fn to_f64(&self) -> f64 {
+		let val = self
+			.mag
+			.iter()
+			.rev()
+			.fold(0.0, |acc, l| acc * 4294967296.0 + *l as f64);
+		match self.neg {
+			true => -val,
+			false => val,
+		}
+	}
+
+	pub fn neg(&self) -> Self {
+		BigInt::from_parts(!self.neg, self.mag.clone())
+	}
+
+	pub fn add(&self, other: &BigInt) -> Self {
+		if self.neg == other.neg {
+			return BigInt::from_parts(self.neg, add_mag(&self.mag, &other.mag));
+		}
+		match cmp_mag(&self.mag, &other.mag) {
+			Ordering::Less => BigInt::from_parts(other.neg, sub_mag(&other.mag, &self.mag)),
+			_ => BigInt::from_parts(self.neg, sub_mag(&self.mag, &other.mag)),
+		}
+	}
+
+	pub fn sub(&self, other: &BigInt) -> Self {
+		self.add(&other.neg())
+	}
+
+	pub fn mul(&self, other: &BigInt) -> Self {
+		BigInt::from_parts(self.neg != other.neg, mul_mag(&self.mag, &other.mag))
+	}
+
+	// truncates towards zero like i64 division, None when dividing by zero
+	pub fn div(&self, other: &BigInt) -> Option<Self> {
+		if other.mag.is_empty() {
+			return None;
+		}
+		let (q, _) = div_rem_mag(&self.mag, &other.mag);
+		Some(BigInt::from_parts(self.neg != other.neg, q))
+	}
+
+	// takes the sign of the dividend like i64 remainder, None when dividing by zero
+	pub fn rem(&self, other: &BigInt) -> Option<Self> {
+		if other.mag.is_empty() {
+			return None;
+		}
+		let (_, r) = div_rem_mag(&self.mag, &other.mag);
+		Some(BigInt::from_parts(self.neg, r))
+	}
+
+	pub fn string(&self) -> String {
+		let mut mag = self.mag.clone();
+		let mut chunks = Vec::new();
+		while !mag.is_empty() {
+			let (q, r) = div_rem_small(&mag, 1_000_000_000);
+			chunks.push(r);
+			mag = q;
+		}
+		let mut out = match self.neg {
+			true => String::from("-"),
+			false => String::new(),
+		};
+		match chunks.pop() {
+			None => out.push('0'),
+			Some(first) => out += first.to_string().as_str(),
+		}
+		chunks
+			.iter()
+			.rev()
+			.for_each(|c| out += format!("{:09}", c).as_str());
+		out
+	}
+}
+
+impl PartialOrd for BigInt {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for BigInt {
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (self.neg, other.neg) {
+			(false, true) => Ordering::Greater,
+			(true, false) => Ordering::Less,
+			(false, false) => cmp_mag(&self.mag, &other.mag),
+			(true, true) => cmp_mag(&other.mag, &self.mag),
+		}
+	}
+}
+
+fn trim(mut mag: Vec<u32>) -> Vec<u32> {
+	while mag.last() == Some(&0) {
+		mag.pop();
+	}
+	mag
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+	a.len()
+		.cmp(&b.len())
+		.then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+	let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+	let mut carry = 0u64;
+	for i in 0..a.len().max(b.len()) {
+		let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+		out.push(sum as u32);
+		carry = sum >> 32;
+	}
+	if carry > 0 {
+		out.push(carry as u32);
+	}
+	out
+}
+
+// requires a >= b
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+	let mut out = Vec::with_capacity(a.len());
+	let mut borrow = 0i64;
+	for (i, l) in a.iter().enumerate() {
+		let mut diff = *l as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+		borrow = 0;
+		if diff < 0 {
+			diff += 1 << 32;
+			borrow = 1;
+		}
+		out.push(diff as u32);
+	}
+	trim(out)
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+	let mut out = vec![0u32; a.len() + b.len()];
+	for (i, x) in a.iter().enumerate() {
+		let mut carry = 0u64;
+		for (j, y) in b.iter().enumerate() {
+			let cur = out[i + j] as u64 + *x as u64 * *y as u64 + carry;
+			out[i + j] = cur as u32;
+			carry = cur >> 32;
+		}
+		out[i + b.len()] = carry as u32;
+	}
+	trim(out)
+}
+
+fn div_rem_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+	let mut q = vec![0u32; a.len()];
+	let mut rem = 0u64;
+	for i in (0..a.len()).rev() {
+		let cur = (rem << 32) | a[i] as u64;
+		q[i] = (cur / d as u64) as u32;
+		rem = cur % d as u64;
+	}
+	(trim(q), rem as u32)
+}
+
+// shift and subtract long division, b must not be zero
+fn div_rem_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+	if b.len() == 1 {
+		let (q, r) = div_rem_small(a, b[0]);
+		return (q, trim(vec![r]));
+	}
+	let mut q = vec![0u32; a.len()];
+	let mut r: Vec<u32> = Vec::new();
+	for bit in (0..a.len() * 32).rev() {
+		r = shl_one(&r, (a[bit / 32] >> (bit % 32)) & 1);
+		if cmp_mag(&r, b) != Ordering::Less {
+			r = sub_mag(&r, b);
+			q[bit / 32] |= 1 << (bit % 32);
+		}
+	}
+	(trim(q), r)
+}
+
+fn shl_one(a: &[u32], low: u32) -> Vec<u32> {
+	let mut out = Vec::with_capacity(a.len() + 1);
+	let mut carry = low;
+	for l in a {
+		out.push((l << 1) | carry);
+		carry = l >> 31;
+	}
+	if carry > 0 {
+		out.push(carry);
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// the product of two i64s both as a BigInt and as the exact i128
+	fn big(a: i64, b: i64) -> (BigInt, i128) {
+		(
+			BigInt::from_i64(a).mul(&BigInt::from_i64(b)),
+			a as i128 * b as i128,
+		)
+	}
+
+	fn operands() -> Vec<(BigInt, i128)> {
+		[
+			(0, 1),
+			(7, 1),
+			(-7, 1),
+			(i64::MAX, 3),
+			(i64::MIN, 5),
+			(-4294967296, 4294967297),
+			(i64::MAX, i64::MIN),
+			(i64::MIN, i64::MIN),
+		]
+		.into_iter()
+		.map(|(a, b)| big(a, b))
+		.collect()
+	}
+
+	#[test]
+	fn add_sub_with_mixed_signs() {
+		let (x, y) = (operands(), operands());
+		for (a, ai) in &x {
+			for (b, bi) in &y {
+				let (Some(sum), Some(diff)) = (ai.checked_add(*bi), ai.checked_sub(*bi)) else {
+					continue;
+				};
+				assert_eq!(a.add(b).string(), sum.to_string(), "{} + {}", ai, bi);
+				assert_eq!(a.sub(b).string(), diff.to_string(), "{} - {}", ai, bi);
+			}
+		}
+	}
+
+	#[test]
+	fn mul_with_mixed_signs() {
+		for a in [0, 1, -1, 12345, -4294967296, i64::MAX, i64::MIN] {
+			for b in [0, -1, 99, -4294967297, i64::MAX, i64::MIN] {
+				let (product, expected) = big(a, b);
+				assert_eq!(product.string(), expected.to_string(), "{} * {}", a, b);
+			}
+		}
+		let (a, _) = big(i64::MIN, i64::MIN);
+		assert_eq!(
+			a.mul(&a).string(),
+			"7237005577332262213973186563042994240829374041602535252466099000494570602496"
+		);
+	}
+
+	#[test]
+	fn div_rem_truncate_towards_zero() {
+		let (x, y) = (operands(), operands());
+		for (a, ai) in &x {
+			for (b, bi) in &y {
+				match bi {
+					0 => {
+						assert!(a.div(b).is_none());
+						assert!(a.rem(b).is_none());
+					}
+					_ => {
+						assert_eq!(
+							a.div(b).unwrap().string(),
+							(ai / bi).to_string(),
+							"{} / {}",
+							ai,
+							bi
+						);
+						assert_eq!(
+							a.rem(b).unwrap().string(),
+							(ai % bi).to_string(),
+							"{} % {}",
+							ai,
+							bi
+						);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn to_i64_boundaries() {
+		let max = BigInt::from_i64(i64::MAX);
+		let min = BigInt::from_i64(i64::MIN);
+		let one = BigInt::from_i64(1);
+		let minus_one = BigInt::from_i64(-1);
+		assert_eq!(max.to_i64(), Some(i64::MAX));
+		assert_eq!(min.to_i64(), Some(i64::MIN));
+		assert_eq!(max.add(&one).to_i64(), None);
+		assert_eq!(max.add(&one).string(), "9223372036854775808");
+		assert_eq!(min.sub(&one).to_i64(), None);
+		assert_eq!(max.add(&one).neg().to_i64(), Some(i64::MIN));
+		let quotient = min.div(&minus_one).unwrap();
+		assert_eq!(quotient.to_i64(), None);
+		assert_eq!(quotient.string(), "9223372036854775808");
+		assert_eq!(min.rem(&minus_one).unwrap().to_i64(), Some(0));
+	}
+
+	#[test]
+	fn string_output() {
+		assert_eq!(BigInt::from_i64(0).string(), "0");
+		assert_eq!(BigInt::from_i64(-0).neg().string(), "0");
+		assert_eq!(BigInt::from_i64(-42).string(), "-42");
+		assert_eq!(BigInt::from_i64(i64::MIN).string(), "-9223372036854775808");
+		// inner chunks keep their leading zeros
+		let (a, _) = big(1_000_000_000, 1_000_000_007);
+		assert_eq!(a.string(), "1000000007000000000");
+		let (b, _) = big(-1_000_000_000, 1_000_000_000);
+		assert_eq!(
+			b.mul(&BigInt::from_i64(10)).string(),
+			"-10000000000000000000"
+		);
+	}
+}
@@ -1,134 +1,274 @@
+pub mod bigint;
+
 use crate::ast::Node;
+use crate::code::{Instructions, LineTable};
 use crate::eval::builtins::*;
 use crate::object;
 use crate::{ast, eval::EvalError};
-use std::collections::hash_map::DefaultHasher;
+use bigint::BigInt;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::collections::VecDeque;
-use std::hash::Hasher;
+use std::fmt;
 use std::rc::Rc;
-use std::{any::Any, collections::HashMap};
-
-pub trait Hashable {
-	fn hash_key(&self) -> HashKey;
-}
 
 #[derive(PartialEq, Clone, Eq, Hash)]
 pub enum ObjType {
-	ReturnValue,
 	Integer,
+	BigInteger,
+	Float,
 	Boolean,
 	String,
 	Array,
 	Hash,
 	Null,
-	ObjVec,
-	Error,
 	Function,
+	CompiledFunction,
+	Closure,
 	BuiltinFunction,
+	Range,
+	Iterator,
 }
 
 impl ObjType {
 	pub fn string(&self) -> String {
 		match self {
-			ObjType::ReturnValue => String::from("Return Value"),
 			ObjType::Integer => String::from("Integer"),
+			ObjType::BigInteger => String::from("Big Integer"),
+			ObjType::Float => String::from("Float"),
 			ObjType::Boolean => String::from("Boolean"),
 			ObjType::String => String::from("String"),
 			ObjType::Null => String::from("Null"),
-			ObjType::ObjVec => String::from("ObjVec"),
-			ObjType::Error => String::from("Error"),
 			ObjType::Function => String::from("Function"),
+			ObjType::CompiledFunction => String::from("Compiled Function"),
+			ObjType::Closure => String::from("Closure"),
 			ObjType::BuiltinFunction => String::from("Builtin Function"),
 			ObjType::Array => String::from("Array"),
 			ObjType::Hash => String::from("Hash"),
+			ObjType::Range => String::from("Range"),
+			ObjType::Iterator => String::from("Iterator"),
 		}
 	}
 }
 
+// cloning a value is cheap, anything larger than a number is reference counted
+#[derive(Clone)]
+pub enum Value {
+	Integer(i64),
+	// only holds values outside the i64 range, results that fit are turned back into Integer
+	BigInteger(Rc<BigInt>),
+	Float(f64),
+	Boolean(bool),
+	String(Rc<str>),
+	Array(Array),
+	Hash(Hash),
+	// the integers from start up to but not including end
+	Range(i64, i64),
+	Iterator(Rc<Iter>),
+	Function(Rc<Function>),
+	CompiledFunction(Rc<CompiledFunction>),
+	Closure(Rc<Closure>),
+	Builtin(BuiltinFunc),
+	Null,
+}
+
+// errors carry values, which print the same way the repl shows them
+impl fmt::Debug for Value {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.inspect_obj())
+	}
+}
+
+impl Value {
+	pub fn get_type(&self) -> ObjType {
+		match self {
+			Value::Integer(_) => ObjType::Integer,
+			Value::BigInteger(_) => ObjType::BigInteger,
+			Value::Float(_) => ObjType::Float,
+			Value::Boolean(_) => ObjType::Boolean,
+			Value::String(_) => ObjType::String,
+			Value::Array(_) => ObjType::Array,
+			Value::Hash(_) => ObjType::Hash,
+			Value::Range(..) => ObjType::Range,
+			Value::Iterator(_) => ObjType::Iterator,
+			Value::Function(_) => ObjType::Function,
+			Value::CompiledFunction(_) => ObjType::CompiledFunction,
+			Value::Closure(_) => ObjType::Closure,
+			Value::Builtin(_) => ObjType::BuiltinFunction,
+			Value::Null => ObjType::Null,
+		}
+	}
+
+	pub fn inspect_obj(&self) -> String {
+		match self {
+			Value::Integer(val) => val.to_string(),
+			Value::BigInteger(val) => val.string(),
+			// debug formatting always keeps a decimal point or exponent, 2.0 rather than 2
+			Value::Float(val) => format!("{:?}", val),
+			Value::Boolean(val) => val.to_string(),
+			Value::String(val) => val.to_string(),
+			Value::Array(a) => a.inspect(),
+			Value::Hash(h) => h.inspect(),
+			Value::Range(start, end) => format!("range({}, {})", start, end),
+			Value::Iterator(_) => String::from("iterator"),
+			Value::Function(f) => f.inspect(),
+			Value::CompiledFunction(f) => f.inspect(),
+			Value::Closure(c) => format!("Closure[{} free]\n{}", c.free.len(), c.func.inspect()),
+			Value::Builtin(_) => String::from("Builtin Function"),
+			Value::Null => String::from("null"),
+		}
+	}
+
+	// false, null, zero, the empty string and empty collections are falsy, everything else is truthy
+	pub fn is_truthy(&self) -> bool {
+		match self {
+			Value::Integer(val) => *val != 0,
+			Value::Float(val) => *val != 0.0,
+			Value::Boolean(val) => *val,
+			Value::String(val) => !val.is_empty(),
+			Value::Array(a) => !a.mems.borrow().is_empty(),
+			Value::Hash(h) => !h.pairs.borrow().is_empty(),
+			Value::Range(start, end) => start < end,
+			Value::Null => false,
+			_ => true,
+		}
+	}
+
+	// only strings, integers and booleans can be hash keys
+	pub fn hash_key(&self) -> Option<HashKey> {
+		match self {
+			Value::Integer(val) => Some(HashKey::Integer(*val)),
+			Value::BigInteger(val) => Some(HashKey::BigInteger(val.clone())),
+			Value::String(val) => Some(HashKey::String(val.clone())),
+			Value::Boolean(val) => Some(HashKey::Boolean(*val)),
+			_ => None,
+		}
+	}
+}
+
+pub fn integer_obj(val: BigInt) -> Value {
+	match val.to_i64() {
+		Some(val) => Value::Integer(val),
+		None => Value::BigInteger(Rc::new(val)),
+	}
+}
+
+// keys hold the values themselves so two different keys can never collide, a
+// big integer never fits in an i64 so it cannot equal an Integer key
 #[derive(Clone, PartialEq, Eq, Hash)]
-pub struct HashKey {
-	t: ObjType,
-	val: u64,
+pub enum HashKey {
+	Integer(i64),
+	BigInteger(Rc<BigInt>),
+	Boolean(bool),
+	String(Rc<str>),
 }
 
 #[derive(Clone)]
-pub struct HashPair(pub Box<dyn Obj>, pub Box<dyn Obj>);
+pub struct HashPair(pub Value, pub Value);
 
+// arrays and hashes are shared, cloning one gives another handle to the same
+// contents so an index assignment is seen through every variable holding it
 #[derive(Clone)]
 pub struct Hash {
-	pub pairs: HashMap<HashKey, HashPair>,
+	pub pairs: Rc<RefCell<HashMap<HashKey, HashPair>>>,
 }
-impl Obj for Hash {
-	fn get_type(&self) -> ObjType {
-		ObjType::Hash
+impl Hash {
+	pub fn new(pairs: HashMap<HashKey, HashPair>) -> Self {
+		Hash {
+			pairs: Rc::new(RefCell::new(pairs)),
+		}
 	}
 
-	fn inspect_obj(&self) -> String {
+	fn inspect(&self) -> String {
 		let out = self
 			.pairs
+			.borrow()
 			.values()
 			.fold(String::new(), |acc, HashPair(k, v)| {
-				acc + format!(", {} : {}", k.inspect_obj(), v.inspect_obj())
-					.as_str()
+				acc + format!(", {} : {}", k.inspect_obj(), v.inspect_obj()).as_str()
 			});
 		format!("[{}]", out)
 	}
-
-	fn as_any(&self) -> &dyn Any {
-		self
-	}
-
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
-	}
 }
 
 #[derive(Clone)]
 pub struct Array {
-	pub mems: VecDeque<Box<dyn Obj>>,
+	pub mems: Rc<RefCell<VecDeque<Value>>>,
 }
-impl Obj for Array {
-	fn get_type(&self) -> ObjType {
-		ObjType::Array
+impl Array {
+	pub fn new(mems: VecDeque<Value>) -> Self {
+		Array {
+			mems: Rc::new(RefCell::new(mems)),
+		}
 	}
-	fn inspect_obj(&self) -> String {
-		let out = self.mems.iter().fold(String::new(), |acc, m| {
+
+	fn inspect(&self) -> String {
+		let out = self.mems.borrow().iter().fold(String::new(), |acc, m| {
 			acc + ", " + m.inspect_obj().as_str()
 		});
 		String::from("[") + out.as_str() + "]"
 	}
-	fn as_any(&self) -> &dyn Any {
-		self
-	}
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
-	}
 }
 
+// an array is read as the loop goes so members assigned during the loop are seen,
+// the pairs of a hash are taken when the loop starts
 #[derive(Clone)]
-pub struct Builtin {
-	pub func: Rc<Box<dyn (Fn(Vec<Box<dyn Obj>>) -> Result<Box<dyn Obj>, EvalError>)>>,
+pub enum IterSource {
+	Array(Rc<RefCell<VecDeque<Value>>>),
+	Pairs(Vec<HashPair>),
+	Chars(Vec<char>),
+	Range(i64, i64),
 }
-impl Obj for Builtin {
-	fn get_type(&self) -> ObjType {
-		ObjType::BuiltinFunction
-	}
-	fn inspect_obj(&self) -> String {
-		String::from("Builtin Function")
-	}
-	fn as_any(&self) -> &dyn Any {
-		self
+
+// the position of a for loop in what it walks over
+pub struct Iter {
+	source: IterSource,
+	pos: Cell<usize>,
+}
+impl Iter {
+	pub fn new(source: IterSource) -> Self {
+		Iter {
+			source,
+			pos: Cell::new(0),
+		}
 	}
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
+
+	// one loop variable gets each member, or each key of a hash, two get the index or key and the member
+	pub fn next(&self, vars: usize) -> Option<Vec<Value>> {
+		let pos = self.pos.get();
+		let index = Value::Integer(pos as i64);
+		let (key, val) = match &self.source {
+			IterSource::Array(mems) => (index, mems.borrow().get(pos)?.clone()),
+			IterSource::Pairs(pairs) => {
+				let HashPair(k, v) = pairs.get(pos)?.clone();
+				(k, v)
+			}
+			IterSource::Chars(chars) => (index, Value::String(chars.get(pos)?.to_string().into())),
+			IterSource::Range(start, end) => {
+				let val = i64::try_from(pos)
+					.ok()
+					.and_then(|p| start.checked_add(p))
+					.filter(|v| v < end)?;
+				(index, Value::Integer(val))
+			}
+		};
+		self.pos.set(pos + 1);
+		match (vars, &self.source) {
+			(1, IterSource::Pairs(_)) => Some(vec![key]),
+			(1, _) => Some(vec![val]),
+			_ => Some(vec![key, val]),
+		}
 	}
 }
 
+// cloning an Env shares its scopes, so a function holds on to the scope it was
+// defined in and sees bindings made there after it was created. a function stored
+// in the scope it captured is a reference cycle and lives until the program exits
 #[derive(Clone)]
 pub struct Env {
-	pub builtins: Rc<HashMap<String, Box<dyn Obj>>>,
-	pub store: HashMap<String, Box<dyn Obj>>,
-	pub outer: Option<Box<Env>>,
+	pub builtins: Rc<HashMap<String, Value>>,
+	store: Rc<RefCell<HashMap<String, Value>>>,
+	outer: Option<Box<Env>>,
 }
 
 impl Env {
@@ -137,111 +277,78 @@ impl Env {
 			None => Rc::new(get_builtins()),
 			Some(ref e) => (e.builtins).clone(),
 		};
-		let store = HashMap::new();
+		let store = Rc::new(RefCell::new(HashMap::new()));
 		Env {
 			builtins,
 			store,
 			outer,
 		}
 	}
-	pub fn get(&self, name: String) -> Result<&Box<dyn Obj>, EvalError> {
-		match self.builtins.get(&name) {
-			Some(bi) => Ok(bi),
+	pub fn get(&self, name: &str) -> Result<Value, EvalError> {
+		match self.builtins.get(name) {
+			Some(bi) => Ok(bi.clone()),
 			None => self.get_desc(name),
 		}
 	}
-	fn get_desc(&self, name: String) -> Result<&Box<dyn Obj>, EvalError> {
-		match (self.store.get(&name), &self.outer) {
-			(None, None) => {
-				Err(EvalError::Undefined(String::from("Identifier not defined")))
-			}
-			(Some(obj), _) => Ok(obj),
-			(None, Some(out)) => out.get(name),
+	fn get_desc(&self, name: &str) -> Result<Value, EvalError> {
+		if let Some(obj) = self.store.borrow().get(name) {
+			return Ok(obj.clone());
 		}
-	}
-
-	pub fn set(&mut self, name: String, val: Box<dyn Obj>) {
-		self.store.insert(name, val);
-	}
-}
-impl Clone for Box<dyn Obj> {
-	fn clone(&self) -> Self {
-		self.clone_into_dyn()
-	}
-}
-
-pub trait Obj: Any {
-	fn get_type(&self) -> ObjType;
-	fn inspect_obj(&self) -> String;
-	fn as_any(&self) -> &dyn Any;
-	fn clone_into_dyn(&self) -> Box<dyn Obj>;
-}
-
-impl Obj for Result<Box<dyn Obj>, EvalError> {
-	fn get_type(&self) -> ObjType {
-		match self {
-			Err(_) => ObjType::Error,
-			Ok(o) => o.get_type(),
+		match &self.outer {
+			None => Err(EvalError::Undefined(format!(
+				"Identifier {} not defined",
+				name
+			))),
+			Some(out) => out.get_desc(name),
 		}
 	}
-	fn inspect_obj(&self) -> String {
-		match self {
-			Err(e) => e.get_err_msg(),
-			Ok(o) => o.inspect_obj(),
-		}
-	}
-	fn as_any(&self) -> &dyn Any {
-		self
-	}
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		panic!();
-	}
-}
 
-#[derive(Clone)]
-pub struct StringObj {
-	pub val: String,
-}
-impl Obj for StringObj {
-	fn get_type(&self) -> ObjType {
-		ObjType::String
+	pub fn set(&mut self, name: String, val: Value) {
+		self.store.borrow_mut().insert(name, val);
 	}
 
-	fn inspect_obj(&self) -> String {
-		self.val.clone()
+	// updates the nearest scope that declared name
+	pub fn assign(&mut self, name: &str, val: Value) -> Result<(), EvalError> {
+		self.check_assignable(name)?;
+		self.assign_desc(name, val)
 	}
-
-	fn as_any(&self) -> &dyn Any {
-		self
+	// the current value of a variable a compound assignment is about to update
+	pub fn get_assignable(&self, name: &str) -> Result<Value, EvalError> {
+		self.check_assignable(name)?;
+		self.get_desc(name).map_err(|_| not_declared(name))
 	}
-
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
+	fn check_assignable(&self, name: &str) -> Result<(), EvalError> {
+		match self.builtins.contains_key(name) {
+			true => Err(EvalError::InvalidAssignment(format!(
+				"Cannot assign to builtin {}",
+				name
+			))),
+			false => Ok(()),
+		}
 	}
-}
-impl Hashable for StringObj {
-	fn hash_key(&self) -> HashKey {
-		let mut h = DefaultHasher::new();
-		self.val.chars().for_each(|c| h.write_u32(c.into()));
-		HashKey {
-			t: ObjType::String,
-			val: h.finish(),
+	fn assign_desc(&self, name: &str, val: Value) -> Result<(), EvalError> {
+		if let Some(slot) = self.store.borrow_mut().get_mut(name) {
+			*slot = val;
+			return Ok(());
+		}
+		match &self.outer {
+			None => Err(not_declared(name)),
+			Some(out) => out.assign_desc(name, val),
 		}
 	}
 }
 
-#[derive(Clone)]
+fn not_declared(name: &str) -> EvalError {
+	EvalError::Undefined(format!("Cannot assign to {}, it was never declared", name))
+}
+
 pub struct Function {
 	pub params: Vec<ast::Identifier>,
 	pub body: ast::BlockStatement,
 	pub env: object::Env,
 }
-impl Obj for Function {
-	fn get_type(&self) -> ObjType {
-		ObjType::Function
-	}
-
-	fn inspect_obj(&self) -> String {
+impl Function {
+	fn inspect(&self) -> String {
 		let params = self
 			.params
 			.iter()
@@ -249,107 +356,42 @@ impl Obj for Function {
 			.fold(String::from(""), |acc, s| acc + "," + s.as_str());
 		String::from("fn(") + &params + ")\n" + &self.body.string() + "\n"
 	}
-
-	fn as_any(&self) -> &dyn Any {
-		self
-	}
-
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
-	}
-}
-
-#[derive(Clone)]
-pub struct ReturnValue {
-	pub val: Box<dyn Obj>,
-}
-impl Obj for ReturnValue {
-	fn get_type(&self) -> ObjType {
-		ObjType::ReturnValue
-	}
-
-	fn inspect_obj(&self) -> String {
-		self.val.inspect_obj()
-	}
-
-	fn as_any(&self) -> &dyn Any {
-		self
-	}
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
-	}
 }
 
-#[derive(Clone)]
-pub struct Integer {
-	pub val: i64,
+// where a new closure finds each of its free variables, in the locals of the
+// frame creating it or in that frame's own free variables
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Capture {
+	Local(usize),
+	Free(usize),
 }
-impl Obj for Integer {
-	fn get_type(&self) -> ObjType {
-		ObjType::Integer
-	}
-	fn inspect_obj(&self) -> String {
-		format!("{0}", self.val)
-	}
 
-	fn as_any(&self) -> &dyn Any {
-		self
-	}
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
-	}
+pub struct CompiledFunction {
+	pub instructions: Instructions,
+	pub lines: LineTable,
+	pub num_locals: usize,
+	pub num_params: usize,
+	pub captures: Vec<Capture>,
 }
-impl Hashable for Integer {
-	fn hash_key(&self) -> HashKey {
-		HashKey {
-			t: ObjType::Integer,
-			val: self.val.clone().try_into().unwrap(),
-		}
+impl CompiledFunction {
+	fn inspect(&self) -> String {
+		format!(
+			"CompiledFunction[{} params, {} locals]\n{}",
+			self.num_params,
+			self.num_locals,
+			self.instructions.string()
+		)
 	}
 }
 
-#[derive(Clone)]
-pub struct Boolean {
-	pub val: bool,
-}
-impl Obj for Boolean {
-	fn get_type(&self) -> ObjType {
-		ObjType::Boolean
-	}
-	fn inspect_obj(&self) -> String {
-		format!("{0}", self.val)
-	}
+// a local variable of a call frame, shared with every closure that captured it
+pub type Slot = Rc<RefCell<Value>>;
 
-	fn as_any(&self) -> &dyn Any {
-		self
-	}
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
-	}
-}
-impl Hashable for Boolean {
-	fn hash_key(&self) -> HashKey {
-		HashKey {
-			t: ObjType::Boolean,
-			val: self.val.clone().try_into().unwrap(),
-		}
-	}
+pub fn new_slot(val: Value) -> Slot {
+	Rc::new(RefCell::new(val))
 }
 
-#[derive(Clone)]
-pub struct Null {}
-impl Obj for Null {
-	fn get_type(&self) -> ObjType {
-		ObjType::Null
-	}
-	fn inspect_obj(&self) -> String {
-		String::from("null")
-	}
-
-	fn as_any(&self) -> &dyn Any {
-		self
-	}
-	fn clone_into_dyn(&self) -> Box<dyn Obj> {
-		Box::new(self.clone())
-	}
+pub struct Closure {
+	pub func: Rc<CompiledFunction>,
+	pub free: Vec<Slot>,
 }
@@ -1,26 +1,36 @@
 use crate::{
 	ast::{self, *},
-	eval::EvalError,
 	lexer::Lexer,
-	token::{Token, TokenType},
+	token::{Span, Token, TokenType},
 };
 use core::iter::Peekable;
 use std::collections::HashMap;
 
 const LOWEST: u8 = 1;
-const EQUALS: u8 = 2;
-const LESSGREATER: u8 = 3;
-const SUM: u8 = 4;
-const PRODUCT: u8 = 5;
-const PREFIX: u8 = 6;
-const CALL: u8 = 7;
-const INDEX: u8 = 8;
+const ASSIGN: u8 = 2;
+const OR: u8 = 3;
+const AND: u8 = 4;
+const EQUALS: u8 = 5;
+const LESSGREATER: u8 = 6;
+const SUM: u8 = 7;
+const PRODUCT: u8 = 8;
+const PREFIX: u8 = 9;
+const CALL: u8 = 10;
+const INDEX: u8 = 11;
 
 fn get_precedence(token_type: &TokenType) -> u8 {
 	match token_type {
+		TokenType::Assign
+		| TokenType::PlusAssign
+		| TokenType::MinusAssign
+		| TokenType::AsteriskAssign
+		| TokenType::SlashAssign
+		| TokenType::PercentAssign => ASSIGN,
+		TokenType::Or => OR,
+		TokenType::And => AND,
 		TokenType::Plus | TokenType::Minus => SUM,
-		TokenType::Asterisk | TokenType::Slash => PRODUCT,
-		TokenType::Lt | TokenType::Gt => LESSGREATER,
+		TokenType::Asterisk | TokenType::Slash | TokenType::Percent => PRODUCT,
+		TokenType::Lt | TokenType::Gt | TokenType::LtEq | TokenType::GtEq => LESSGREATER,
 		TokenType::Eq | TokenType::Noteq => EQUALS,
 		TokenType::Lparen => CALL,
 		TokenType::Lbracket => INDEX,
@@ -31,37 +41,56 @@ pub struct Parser<I: Iterator<Item = char>> {
 	lexer: Peekable<Lexer<I>>,
 	prefix_parse_fn: HashMap<TokenType, ParseFunction<I>>,
 	infix_parse_fn: HashMap<TokenType, ParseInfixFunction<I>>,
+	last_span: Span,
+	consumed: usize,
+	braces: usize,
+	blocks: usize,
+	loops: usize,
+	errors: Vec<ParserError>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParserError {
-	UnexpectedEOF(String),
-	UnexpectedToken(String),
-	IntParseError(String),
-	NoPrefixParseFn(String),
-	NoInfixParseFn(String),
-	UnhandledError,
+	UnexpectedEOF(String, Span),
+	UnexpectedToken(String, Span),
+	IntParseError(String, Span),
+	NoPrefixParseFn(String, Span),
+	NoInfixParseFn(String, Span),
+	LexerError(String, Span),
 }
 impl ParserError {
 	pub fn get_err_msg(&self) -> String {
 		match self {
-			ParserError::UnexpectedEOF(m) => m.to_string(),
-			ParserError::UnexpectedToken(m) => m.to_string(),
-			ParserError::IntParseError(m) => m.to_string(),
-			ParserError::NoPrefixParseFn(m) => m.to_string(),
-			ParserError::NoInfixParseFn(m) => m.to_string(),
-			ParserError::UnhandledError => {
-				String::from("Encoutered an unexpected Error")
-			}
+			ParserError::UnexpectedEOF(m, _) => m.to_string(),
+			ParserError::UnexpectedToken(m, _) => m.to_string(),
+			ParserError::IntParseError(m, _) => m.to_string(),
+			ParserError::NoPrefixParseFn(m, _) => m.to_string(),
+			ParserError::NoInfixParseFn(m, _) => m.to_string(),
+			ParserError::LexerError(m, _) => m.to_string(),
+		}
+	}
+	pub fn get_span(&self) -> Span {
+		match self {
+			ParserError::UnexpectedEOF(_, s) => *s,
+			ParserError::UnexpectedToken(_, s) => *s,
+			ParserError::IntParseError(_, s) => *s,
+			ParserError::NoPrefixParseFn(_, s) => *s,
+			ParserError::NoInfixParseFn(_, s) => *s,
+			ParserError::LexerError(_, s) => *s,
 		}
 	}
 }
 
-type BoxNode = Box<dyn Node>;
-type ResultNode = Result<BoxNode, ParserError>;
+fn lexer_error(token: &Token) -> ParserError {
+	ParserError::LexerError(token.literal.clone(), token.span)
+}
+
+type ResultExpr = Result<Expr, ParserError>;
+type ResultStmt = Result<Stmt, ParserError>;
+type ExprPairs = Vec<(Expr, Expr)>;
 
-type ParseFunction<I> = fn(&mut Parser<I>) -> ResultNode;
-type ParseInfixFunction<I> = fn(&mut Parser<I>, BoxNode) -> ResultNode;
+type ParseFunction<I> = fn(&mut Parser<I>) -> ResultExpr;
+type ParseInfixFunction<I> = fn(&mut Parser<I>, Expr) -> ResultExpr;
 
 macro_rules! prefix_add {
 	($hm:ident, $tt: ident, $fn:ident) => {
@@ -81,6 +110,7 @@ impl<I: Iterator<Item = char>> Parser<I> {
 		let mut prefix_parse_fn: HashMap<TokenType, ParseFunction<I>> = HashMap::new();
 		prefix_add!(prefix_parse_fn, Ident, parse_identifier_expression);
 		prefix_add!(prefix_parse_fn, Int, parse_integer_literal);
+		prefix_add!(prefix_parse_fn, Float, parse_float_literal);
 		prefix_add!(prefix_parse_fn, Bang, parse_prefix_expression);
 		prefix_add!(prefix_parse_fn, Minus, parse_prefix_expression);
 		prefix_add!(prefix_parse_fn, True, parse_boolean);
@@ -97,10 +127,21 @@ impl<I: Iterator<Item = char>> Parser<I> {
 		infix_add!(infix_parse_fn, Minus, parse_infix_expression);
 		infix_add!(infix_parse_fn, Slash, parse_infix_expression);
 		infix_add!(infix_parse_fn, Asterisk, parse_infix_expression);
+		infix_add!(infix_parse_fn, Percent, parse_infix_expression);
 		infix_add!(infix_parse_fn, Eq, parse_infix_expression);
 		infix_add!(infix_parse_fn, Noteq, parse_infix_expression);
 		infix_add!(infix_parse_fn, Lt, parse_infix_expression);
 		infix_add!(infix_parse_fn, Gt, parse_infix_expression);
+		infix_add!(infix_parse_fn, LtEq, parse_infix_expression);
+		infix_add!(infix_parse_fn, GtEq, parse_infix_expression);
+		infix_add!(infix_parse_fn, And, parse_logical_expression);
+		infix_add!(infix_parse_fn, Or, parse_logical_expression);
+		infix_add!(infix_parse_fn, Assign, parse_assign_expression);
+		infix_add!(infix_parse_fn, PlusAssign, parse_assign_expression);
+		infix_add!(infix_parse_fn, MinusAssign, parse_assign_expression);
+		infix_add!(infix_parse_fn, AsteriskAssign, parse_assign_expression);
+		infix_add!(infix_parse_fn, SlashAssign, parse_assign_expression);
+		infix_add!(infix_parse_fn, PercentAssign, parse_assign_expression);
 		infix_add!(infix_parse_fn, Lparen, parse_call_expression);
 		infix_add!(infix_parse_fn, Lbracket, parse_array_infix);
 
@@ -108,107 +149,235 @@ impl<I: Iterator<Item = char>> Parser<I> {
 			lexer,
 			prefix_parse_fn,
 			infix_parse_fn,
+			last_span: Span {
+				line: 1,
+				column: 1,
+				..Span::default()
+			},
+			consumed: 0,
+			braces: 0,
+			blocks: 0,
+			loops: 0,
+			errors: Vec::new(),
 		}
 	}
+
+	fn next_token(&mut self) -> Option<Token> {
+		let token = self.lexer.next()?;
+		self.last_span = token.span;
+		self.consumed += 1;
+		match token.token_type {
+			TokenType::Lbrace => self.braces += 1,
+			TokenType::Rbrace => self.braces = self.braces.saturating_sub(1),
+			_ => (),
+		}
+		Some(token)
+	}
+
+	// skips the rest of a broken statement so that the next one parses cleanly,
+	// stopping at the } of the enclosing block but not at one closing a hash literal
+	fn synchronize(&mut self) {
+		while let Some(token) = self.lexer.peek() {
+			match token.token_type {
+				TokenType::Rbrace if self.blocks > 0 && self.braces <= self.blocks => return,
+				TokenType::Let | TokenType::Return | TokenType::While | TokenType::For => return,
+				TokenType::Semicolon => {
+					self.next_token();
+					return;
+				}
+				_ => {
+					self.next_token();
+				}
+			}
+		}
+	}
+
+	// points just past the last token that was read
+	fn eof_error(&self, msg: &str) -> ParserError {
+		let last = self.last_span;
+		let span = Span {
+			start: last.end,
+			end: last.end,
+			line: last.line,
+			column: last.column + (last.end - last.start),
+		};
+		ParserError::UnexpectedEOF(msg.to_string(), span)
+	}
 	fn peek_precedence(&mut self) -> u8 {
 		let token = self.lexer.peek().unwrap();
 		get_precedence(&token.token_type)
 	}
-	pub fn parse_program(self) -> Program {
-		let statements = self.collect::<Vec<ResultNode>>();
-		Program { statements }
+	pub fn parse_program(mut self) -> Program {
+		let statements = self.by_ref().filter_map(|s| s.ok()).collect();
+		Program {
+			statements,
+			errors: self.errors,
+		}
 	}
 
-	fn parse_hash_literal(&mut self) -> ResultNode {
+	fn parse_hash_literal(&mut self) -> ResultExpr {
 		let tok = self.expect_next_token(TokenType::Lbrace)?;
 
 		let pairs = match self.peek_token() {
 			Ok(Token {
 				token_type: TokenType::Rbrace,
-				literal: _,
-			}) => Ok(Vec::new()),
+				..
+			}) => {
+				self.next_token();
+				Ok(Vec::new())
+			}
 			Ok(_) => self.parse_hash_mems(Vec::new()),
-			Err(_) => Err(ParserError::UnexpectedEOF(String::from("Unexpected EOF"))),
+			Err(e) => Err(e),
 		}?;
 
-		Ok(Box::new(HashLiteral { tok, pairs }))
+		Ok(Expr::Hash(HashLiteral { tok, pairs }))
 	}
 
 	fn parse_hash_mems(
 		&mut self,
-		mut hm: Vec<(Box<dyn Node>, Box<dyn Node>)>,
-	) -> Result<Vec<(Box<dyn Node>, Box<dyn Node>)>, ParserError> {
+		mut hm: ExprPairs,
+	) -> Result<ExprPairs, ParserError> {
 		let k = self.parse_expression(LOWEST)?;
 		self.expect_next_token(TokenType::Colon)?;
 		let v = self.parse_expression(LOWEST)?;
 
 		hm.push((k, v));
 
-		match self.lexer.next() {
-			Some(Token {
-				token_type: TokenType::Comma,
-				literal: _,
-			}) => self.parse_hash_mems(hm),
-			Some(Token {
-				token_type: TokenType::Rbrace,
-				literal: _,
-			}) => Ok(hm),
-			Some(_) => Err(ParserError::UnexpectedToken(String::from(
-				"Expected : or }",
-			))),
-			None => Err(ParserError::UnexpectedEOF(String::from("UnexpectedEOF"))),
+		let token = self.peek_token()?;
+		match token.token_type {
+			TokenType::Comma => {
+				self.next_token();
+				self.parse_hash_mems(hm)
+			}
+			TokenType::Rbrace => {
+				self.next_token();
+				Ok(hm)
+			}
+			_ => Err(ParserError::UnexpectedToken(
+				format!("Unexpected {}, expected , or }}", token.literal),
+				token.span,
+			)),
 		}
 	}
 
-	fn parse_array_literal(&mut self) -> ResultNode {
+	fn parse_array_literal(&mut self) -> ResultExpr {
 		let tok = self.expect_next_token(TokenType::Lbracket)?;
 		let mems = match self.peek_token()?.token_type {
-			TokenType::Rbracket => Vec::new(),
+			TokenType::Rbracket => {
+				self.next_token();
+				Vec::new()
+			}
 			_ => self.parse_expression_list(&mut Vec::new())?,
 		};
-		Ok(Box::new(ArrayLiteral { tok, mems }))
+		Ok(Expr::Array(ArrayLiteral { tok, mems }))
 	}
-	fn parse_expression_list(
-		&mut self,
-		left: &mut Vec<Box<dyn Node>>,
-	) -> Result<Vec<Box<dyn Node>>, ParserError> {
+	fn parse_expression_list(&mut self, left: &mut Vec<Expr>) -> Result<Vec<Expr>, ParserError> {
 		left.push(self.parse_expression(LOWEST)?);
 
-		match self
-			.lexer
-			.next()
-			.ok_or(ParserError::UnexpectedEOF(String::from("Unexpected EOF")))?
-			.token_type
-		{
-			TokenType::Comma => self.parse_expression_list(left),
-			TokenType::Rbracket => Ok(left.to_vec()),
-			tt => Err(ParserError::UnexpectedToken(String::from(format!(
-				"{} Unexpected token",
-				tt.get_name()
-			)))),
+		let token = self.peek_token()?;
+		match token.token_type {
+			TokenType::Comma => {
+				self.next_token();
+				self.parse_expression_list(left)
+			}
+			TokenType::Rbracket => {
+				self.next_token();
+				Ok(left.to_vec())
+			}
+			_ => Err(ParserError::UnexpectedToken(
+				format!("Unexpected {}, expected , or ]", token.literal),
+				token.span,
+			)),
 		}
 	}
 
-	fn parse_string_literal(&mut self) -> ResultNode {
+	fn parse_string_literal(&mut self) -> ResultExpr {
 		let token = self.expect_next_token(TokenType::String)?;
 		let val = token.literal.clone();
-		Ok(Box::new(StringLiteral { token, val }))
+		Ok(Expr::String(StringLiteral { token, val }))
 	}
 
-	fn parse_let_statement(&mut self) -> ResultNode {
+	fn parse_let_statement(&mut self) -> ResultStmt {
 		let token = self.expect_next_token(TokenType::Let)?;
 		let name = self.parse_identifier()?;
 		self.expect_next_token(TokenType::Assign)?;
 		let value = self.parse_expression(LOWEST)?;
 		self.expect_next_token(TokenType::Semicolon)?;
-		Ok(Box::new(LetStatement { token, name, value }))
+		Ok(Stmt::Let(LetStatement { token, name, value }))
 	}
 
-	fn parse_return_statement(&mut self) -> ResultNode {
+	fn parse_return_statement(&mut self) -> ResultStmt {
 		let token = self.expect_next_token(TokenType::Return)?;
 		let value = self.parse_expression(LOWEST)?;
 		self.expect_next_token(TokenType::Semicolon)?;
-		Ok(Box::new(ReturnStatement { token, value }))
+		Ok(Stmt::Return(ReturnStatement { token, value }))
+	}
+
+	fn parse_while_statement(&mut self) -> ResultStmt {
+		let token = self.expect_next_token(TokenType::While)?;
+		self.expect_next_token(TokenType::Lparen)?;
+		let condition = self.parse_expression(LOWEST)?;
+		self.expect_next_token(TokenType::Rparen)?;
+		let body = self.parse_loop_body()?;
+		Ok(Stmt::While(WhileStatement {
+			token,
+			condition,
+			body,
+		}))
+	}
+
+	fn parse_for_statement(&mut self) -> ResultStmt {
+		let token = self.expect_next_token(TokenType::For)?;
+		self.expect_next_token(TokenType::Lparen)?;
+		let mut vars = vec![self.parse_identifier()?];
+		if self.peek_token_is_type(TokenType::Comma)? {
+			self.next_token();
+			vars.push(self.parse_identifier()?);
+		}
+		self.expect_next_token(TokenType::In)?;
+		let iterable = self.parse_expression(LOWEST)?;
+		self.expect_next_token(TokenType::Rparen)?;
+		let body = self.parse_loop_body()?;
+		Ok(Stmt::For(ForStatement {
+			token,
+			vars,
+			iterable,
+			body,
+		}))
+	}
+
+	// the ; after the closing brace is optional
+	fn parse_loop_body(&mut self) -> Result<BlockStatement, ParserError> {
+		self.loops += 1;
+		let body = self.parse_block_statement();
+		self.loops -= 1;
+		let body = body?;
+
+		if let Some(Token {
+			token_type: TokenType::Semicolon,
+			..
+		}) = self.lexer.peek()
+		{
+			self.next_token();
+		}
+		Ok(body)
+	}
+
+	fn parse_loop_control(&mut self) -> ResultStmt {
+		let token = self
+			.next_token()
+			.ok_or_else(|| self.eof_error("Expected break or continue found EOF"))?;
+		if self.loops == 0 {
+			return Err(ParserError::UnexpectedToken(
+				format!("{} can only be used inside a loop", token.literal),
+				token.span,
+			));
+		}
+		self.expect_next_token(TokenType::Semicolon)?;
+		match token.token_type {
+			TokenType::Break => Ok(Stmt::Break(BreakStatement { token })),
+			_ => Ok(Stmt::Continue(ContinueStatement { token })),
+		}
 	}
 
 	fn parse_identifier(&mut self) -> Result<ast::Identifier, ParserError> {
@@ -219,48 +388,56 @@ impl<I: Iterator<Item = char>> Parser<I> {
 		})
 	}
 
-	fn parse_boolean(&mut self) -> ResultNode {
+	fn parse_boolean(&mut self) -> ResultExpr {
 		let value = self.peek_token_is_type(TokenType::True)?;
-		let token = self.lexer.next().ok_or(ParserError::UnexpectedEOF(
-			"expected Boolean token found EOF".to_string(),
-		))?;
+		let token = self
+			.next_token()
+			.ok_or_else(|| self.eof_error("expected Boolean token found EOF"))?;
 
-		Ok(Box::new(BooleanLiteral { token, value }))
+		Ok(Expr::Boolean(BooleanLiteral { token, value }))
 	}
 
-	fn parse_if_statement(&mut self) -> ResultNode {
-		let token = self.lexer.next().ok_or(ParserError::UnexpectedEOF(
-			"Expected If Statement found EOF".to_string(),
-		))?;
+	fn parse_if_statement(&mut self) -> ResultExpr {
+		let token = self
+			.next_token()
+			.ok_or_else(|| self.eof_error("Expected If Statement found EOF"))?;
 
 		self.expect_next_token(TokenType::Lparen)?;
 
-		let condition = self.parse_expression(LOWEST)?;
+		let condition = Box::new(self.parse_expression(LOWEST)?);
 
 		self.expect_next_token(TokenType::Rparen)?;
 
 		let consequence = self.parse_block_statement()?;
 		let alternative = self.parse_else_block()?;
 
-		let expression = IfExpression {
+		Ok(Expr::If(IfExpression {
 			token,
 			condition,
 			consequence,
 			alternative,
-		};
-		let expression = Box::new(expression);
-		Ok(expression)
+		}))
 	}
 
 	fn parse_else_block(&mut self) -> Result<Option<BlockStatement>, ParserError> {
 		if !self.peek_token_is_type(TokenType::Else)? {
 			return Ok(None);
 		}
-		self.lexer.next().unwrap();
-		Ok(Some(self.parse_block_statement()?))
+		self.next_token();
+		if !self.peek_token_is_type(TokenType::If)? {
+			return Ok(Some(self.parse_block_statement()?));
+		}
+
+		// else if becomes an else block holding just the nested if
+		let token = self.peek_token()?;
+		let expression = self.parse_if_statement()?;
+		Ok(Some(BlockStatement {
+			token: token.clone(),
+			statements: vec![Stmt::Expression(ExpressionStatement { token, expression })],
+		}))
 	}
 
-	fn parse_grouped_expression(&mut self) -> ResultNode {
+	fn parse_grouped_expression(&mut self) -> ResultExpr {
 		self.expect_next_token(TokenType::Lparen)?;
 		let expression = self.parse_expression(LOWEST);
 
@@ -268,140 +445,213 @@ impl<I: Iterator<Item = char>> Parser<I> {
 		expression
 	}
 
-	fn parse_expression(&mut self, precedence: u8) -> ResultNode {
+	fn parse_expression(&mut self, precedence: u8) -> ResultExpr {
 		let token = self.peek_token()?;
 
-		let prefix = self.prefix_parse_fn.get(&token.token_type).ok_or_else(|| {
-			self.lexer.next();
-			return ParserError::NoPrefixParseFn(format!(
-				"no prefix parse function for {}",
-				token.token_type.get_name()
-			));
-		})?;
-		let mut left_exp: BoxNode = prefix(self)?;
-
-		left_exp = self.parse_expression_infix(left_exp, precedence)?;
+		let prefix = match self.prefix_parse_fn.get(&token.token_type) {
+			Some(prefix) => prefix,
+			None => {
+				return Err(ParserError::NoPrefixParseFn(
+					format!(
+						"no prefix parse function for {}",
+						token.token_type.get_name()
+					),
+					token.span,
+				));
+			}
+		};
+		let left_exp = prefix(self)?;
 
-		Ok(left_exp)
+		self.parse_expression_infix(left_exp, precedence)
 	}
 
-	fn parse_array_infix(&mut self, left: BoxNode) -> ResultNode {
+	fn parse_array_infix(&mut self, left: Expr) -> ResultExpr {
 		let tok = self.expect_next_token(TokenType::Lbracket)?;
-		let index = self.parse_expression(LOWEST)?;
+		let index = Box::new(self.parse_expression(LOWEST)?);
 		self.expect_next_token(TokenType::Rbracket)?;
-		Ok(Box::new(IndexExpression { tok, left, index }))
+		Ok(Expr::Index(IndexExpression {
+			tok,
+			left: Box::new(left),
+			index,
+		}))
 	}
 
-	fn parse_expression_infix(&mut self, left_exp: BoxNode, precedence: u8) -> ResultNode {
+	fn parse_expression_infix(&mut self, left_exp: Expr, precedence: u8) -> ResultExpr {
 		if self.peek_token_is_type(TokenType::Semicolon)?
-			|| &precedence >= &self.peek_precedence()
+			|| precedence >= self.peek_precedence()
 		{
 			return Ok(left_exp);
 		}
 		let peek_token = self.peek_token()?;
 
-		let infix = self
-			.infix_parse_fn
-			.get(&peek_token.token_type)
-			.ok_or_else(|| {
-				self.lexer.next();
-				return ParserError::NoInfixParseFn(format!(
-					"no infix parse function for {}",
-					peek_token.token_type.get_name()
+		let infix = match self.infix_parse_fn.get(&peek_token.token_type) {
+			Some(infix) => infix,
+			None => {
+				return Err(ParserError::NoInfixParseFn(
+					format!(
+						"no infix parse function for {}",
+						peek_token.token_type.get_name()
+					),
+					peek_token.span,
 				));
-			})?;
+			}
+		};
 		let left_exp = infix(self, left_exp)?;
 		self.parse_expression_infix(left_exp, precedence)
 	}
 	fn expect_next_token(&mut self, expected: TokenType) -> Result<Token, ParserError> {
-		match self.lexer.next() {
-			Some(token) if token.token_type == expected => Ok(token),
-			Some(token) => Err(ParserError::UnexpectedToken(format!(
-				"Unexpected {}, expected {}",
-				token.literal,
-				expected.get_name()
-			))),
-			None => Err(ParserError::UnexpectedEOF(
-				"Expected token none found".to_string(),
+		match self.lexer.peek() {
+			Some(token) if token.token_type == TokenType::Error => Err(lexer_error(token)),
+			Some(token) if token.token_type == expected => Ok(self.next_token().unwrap()),
+			Some(token) => Err(ParserError::UnexpectedToken(
+				format!(
+					"Unexpected {}, expected {}",
+					token.literal,
+					expected.get_name()
+				),
+				token.span,
 			)),
+			None => Err(self.eof_error("Expected token none found")),
 		}
 	}
 
 	fn peek_token(&mut self) -> Result<Token, ParserError> {
-		self.lexer
-			.peek()
-			.ok_or(ParserError::UnexpectedEOF("Unexpected EOF".to_string()))
-			.cloned()
+		match self.lexer.peek() {
+			Some(token) if token.token_type == TokenType::Error => Err(lexer_error(token)),
+			Some(token) => Ok(token.clone()),
+			None => Err(self.eof_error("Unexpected EOF")),
+		}
 	}
 
 	fn peek_token_is_type(&mut self, expected: TokenType) -> Result<bool, ParserError> {
-		let token = self.lexer.peek().ok_or(ParserError::UnexpectedEOF(format!(
-			"expected {} found EOF",
-			expected.get_name()
-		)))?;
-		Ok(token.token_type == expected)
+		match self.lexer.peek() {
+			Some(token) => Ok(token.token_type == expected),
+			None => Err(self.eof_error(&format!("expected {} found EOF", expected.get_name()))),
+		}
 	}
-	fn parse_identifier_expression(&mut self) -> ResultNode {
+	fn parse_identifier_expression(&mut self) -> ResultExpr {
 		let token = self.expect_next_token(TokenType::Ident)?;
 		let value = token.literal.clone();
-		Ok(Box::new(Identifier { token, value }) as Box<dyn Node>)
+		Ok(Expr::Identifier(Identifier { token, value }))
 	}
 
-	fn parse_expression_statement(&mut self) -> ResultNode {
-		let token = self
-			.lexer
-			.peek()
-			.ok_or(ParserError::UnhandledError)?
-			.to_owned();
+	fn parse_expression_statement(&mut self) -> ResultStmt {
+		let token = self.peek_token()?;
 		let expression = self.parse_expression(LOWEST)?;
 		self.expect_next_token(TokenType::Semicolon)?;
-		Ok(Box::new(ExpressionStatement { expression, token }) as Box<dyn Node>)
+		Ok(Stmt::Expression(ExpressionStatement { expression, token }))
 	}
 
-	fn parse_integer_literal(&mut self) -> ResultNode {
+	fn parse_integer_literal(&mut self) -> ResultExpr {
 		let token = self.expect_next_token(TokenType::Int)?;
 		let value = token.literal.parse().map_err(|_| {
-			ParserError::IntParseError(format!(
-				"Could not parse {} as integer",
-				token.literal
-			))
+			ParserError::IntParseError(
+				format!("Could not parse {} as integer", token.literal),
+				token.span,
+			)
+		})?;
+		Ok(Expr::Integer(IntegerLiteral { token, value }))
+	}
+
+	fn parse_float_literal(&mut self) -> ResultExpr {
+		let token = self.expect_next_token(TokenType::Float)?;
+		let value = token.literal.parse().map_err(|_| {
+			ParserError::IntParseError(
+				format!("Could not parse {} as float", token.literal),
+				token.span,
+			)
 		})?;
-		Ok(Box::new(IntegerLiteral { token, value }))
+		Ok(Expr::Float(FloatLiteral { token, value }))
 	}
 
-	fn parse_prefix_expression(&mut self) -> ResultNode {
-		let token = self.lexer.next().unwrap();
+	fn parse_prefix_expression(&mut self) -> ResultExpr {
+		let token = self.next_token().unwrap();
 		let operator = token.literal.clone();
-		let right = self.parse_expression(PREFIX)?;
-		Ok(Box::new(PrefixExpression {
+		let right = Box::new(self.parse_expression(PREFIX)?);
+		Ok(Expr::Prefix(PrefixExpression {
 			token,
 			operator,
 			right,
 		}))
 	}
 
-	fn parse_infix_expression(&mut self, left: BoxNode) -> ResultNode {
-		let token = self.lexer.next().unwrap();
+	fn parse_infix_expression(&mut self, left: Expr) -> ResultExpr {
+		let token = self.next_token().unwrap();
 		let precedence = get_precedence(&token.token_type);
-		let right = self.parse_expression(precedence)?;
+		let right = Box::new(self.parse_expression(precedence)?);
 		let operator = token.literal.clone();
 
-		Ok(Box::new(InfixExpression {
+		Ok(Expr::Infix(InfixExpression {
 			token,
-			left,
+			left: Box::new(left),
 			operator,
 			right,
 		}))
 	}
 
-	fn parse_function_literal(&mut self) -> ResultNode {
+	fn parse_logical_expression(&mut self, left: Expr) -> ResultExpr {
+		let token = self.next_token().unwrap();
+		let precedence = get_precedence(&token.token_type);
+		let right = Box::new(self.parse_expression(precedence)?);
+		let operator = token.literal.clone();
+
+		Ok(Expr::Logical(LogicalExpression {
+			token,
+			left: Box::new(left),
+			operator,
+			right,
+		}))
+	}
+
+	// right associative, a = b = 1 assigns 1 to both
+	fn parse_assign_expression(&mut self, left: Expr) -> ResultExpr {
+		let token = self.next_token().unwrap();
+		if !matches!(left, Expr::Identifier(_) | Expr::Index(_)) {
+			return Err(ParserError::UnexpectedToken(
+				format!(
+					"Cannot assign to {}, only variables and indexes can be assigned",
+					left.string()
+				),
+				token.span,
+			));
+		}
+		let value = Box::new(self.parse_expression(ASSIGN - 1)?);
+		let operator = token.literal.clone();
+
+		match left {
+			Expr::Identifier(name) => Ok(Expr::Assign(AssignExpression {
+				token,
+				name,
+				operator,
+				value,
+			})),
+			Expr::Index(IndexExpression {
+				tok: _,
+				left,
+				index,
+			}) => Ok(Expr::IndexAssign(IndexAssignExpression {
+				token,
+				left,
+				index,
+				operator,
+				value,
+			})),
+			_ => unreachable!("checked before parsing the value"),
+		}
+	}
+
+	fn parse_function_literal(&mut self) -> ResultExpr {
 		let token = self.expect_next_token(TokenType::Function)?;
 		self.expect_next_token(TokenType::Lparen)?;
 
 		let params = self.parse_funcion_parameters(Vec::new())?;
-		let body = self.parse_block_statement()?;
+		// a loop around the function does not make break valid inside its body
+		let loops = std::mem::take(&mut self.loops);
+		let body = self.parse_block_statement();
+		self.loops = loops;
+		let body = body?;
 
-		Ok(Box::new(FunctionLiteral {
+		Ok(Expr::Function(FunctionLiteral {
 			token,
 			params,
 			body,
@@ -410,29 +660,34 @@ impl<I: Iterator<Item = char>> Parser<I> {
 
 	fn parse_block_statement(&mut self) -> Result<BlockStatement, ParserError> {
 		let token = self.expect_next_token(TokenType::Lbrace)?;
-		let statements = self.collect_statements(Vec::new(), TokenType::Rbrace)?;
-		Ok(BlockStatement { token, statements })
+		self.blocks += 1;
+		let statements = self.collect_statements(Vec::new(), TokenType::Rbrace);
+		self.blocks -= 1;
+		Ok(BlockStatement {
+			token,
+			statements: statements?,
+		})
 	}
 
 	fn collect_statements(
 		&mut self,
-		mut acc: Vec<ResultNode>,
+		mut acc: Vec<Stmt>,
 		end_token: TokenType,
-	) -> Result<Vec<ResultNode>, ParserError> {
-		let peek_tok = self
-			.lexer
-			.peek()
-			.ok_or(ParserError::UnexpectedEOF("Unexpected EOF".to_string()))?;
+	) -> Result<Vec<Stmt>, ParserError> {
+		let peek_tok = self.peek_token()?;
 
 		if peek_tok.token_type == end_token {
 			self.expect_next_token(end_token)?;
 			return Ok(acc);
 		}
 
-		let stmt = self
+		// a broken statement is recorded in errors and left out of the block
+		if let Ok(stmt) = self
 			.next()
-			.ok_or(ParserError::UnexpectedEOF("Unexpected EOF".to_string()))?;
-		acc.push(stmt);
+			.ok_or_else(|| self.eof_error("Unexpected EOF"))?
+		{
+			acc.push(stmt);
+		}
 		self.collect_statements(acc, end_token)
 	}
 
@@ -442,7 +697,7 @@ impl<I: Iterator<Item = char>> Parser<I> {
 	) -> Result<Vec<Identifier>, ParserError> {
 		let peek_tok = self.peek_token()?;
 		if peek_tok.token_type == TokenType::Rparen {
-			self.lexer.next();
+			self.next_token();
 			return Ok(params);
 		}
 
@@ -454,52 +709,73 @@ impl<I: Iterator<Item = char>> Parser<I> {
 
 		let peek_tok = self.peek_token()?;
 		if peek_tok.token_type == TokenType::Comma {
-			self.lexer.next();
+			self.next_token();
 		}
 
-		return self.parse_funcion_parameters(params);
+		self.parse_funcion_parameters(params)
 	}
-	fn parse_call_expression(&mut self, function: BoxNode) -> ResultNode {
+	fn parse_call_expression(&mut self, function: Expr) -> ResultExpr {
 		let token = self.expect_next_token(TokenType::Lparen)?;
 		let args = self.parse_call_args(Vec::new())?;
-		Ok(Box::new(CallExpression {
+		Ok(Expr::Call(CallExpression {
 			token,
-			function,
+			function: Box::new(function),
 			args,
 		}))
 	}
-	fn parse_call_args(&mut self, mut left: Vec<BoxNode>) -> Result<Vec<BoxNode>, ParserError> {
+	fn parse_call_args(&mut self, mut left: Vec<Expr>) -> Result<Vec<Expr>, ParserError> {
 		let peek_tok = self.peek_token()?;
 
 		if peek_tok.token_type == TokenType::Rparen {
-			self.lexer.next();
+			self.next_token();
 			return Ok(left);
 		}
 
-		let exp = self.parse_expression(CALL)?;
+		let exp = self.parse_expression(LOWEST)?;
 		left.push(exp);
 
 		let peek_tok = self.peek_token()?;
 		if peek_tok.token_type == TokenType::Comma {
-			self.lexer.next();
-			return self.parse_call_args(left);
+			self.next_token();
+			self.parse_call_args(left)
 		} else if peek_tok.token_type == TokenType::Rparen {
-			self.lexer.next();
-			return Ok(left);
+			self.next_token();
+			Ok(left)
 		} else {
-			return Err(ParserError::UnhandledError);
+			Err(ParserError::UnexpectedToken(
+				format!("Unexpected {}, expected , or )", peek_tok.literal),
+				peek_tok.span,
+			))
 		}
 	}
 }
 
 impl<I: Iterator<Item = char>> Iterator for Parser<I> {
-	type Item = ResultNode;
+	type Item = ResultStmt;
 	fn next(&mut self) -> Option<Self::Item> {
+		let consumed = self.consumed;
+		let errors = self.errors.len();
 		let token = self.lexer.peek()?;
-		match token.token_type {
-			TokenType::Let => Some(self.parse_let_statement()),
-			TokenType::Return => Some(self.parse_return_statement()),
-			_ => Some(self.parse_expression_statement()),
+		let statement = match token.token_type {
+			TokenType::Let => self.parse_let_statement(),
+			TokenType::Return => self.parse_return_statement(),
+			TokenType::While => self.parse_while_statement(),
+			TokenType::For => self.parse_for_statement(),
+			TokenType::Break | TokenType::Continue => self.parse_loop_control(),
+			_ => self.parse_expression_statement(),
+		};
+		match statement {
+			Err(e) => {
+				self.errors.push(e.clone());
+				if self.consumed == consumed {
+					self.next_token();
+				}
+				self.synchronize();
+				Some(Err(e))
+			}
+			// a nested block dropped a broken statement, so this one is incomplete
+			Ok(_) if self.errors.len() > errors => Some(Err(self.errors[errors].clone())),
+			ok => Some(ok),
 		}
 	}
 }
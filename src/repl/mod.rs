@@ -1,19 +1,21 @@
-use crate::eval::EvalError;
+use crate::diagnostics::Diagnostic;
+use crate::eval::{eval_node, EvalError, Unwind};
 use crate::lexer::Lexer;
 use crate::object::Env;
 use crate::parser::Parser;
+use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::io;
 use std::io::stdin;
 use std::io::Write;
 use std::iter::*;
-
-use crate::object::ObjType;
+use std::rc::Rc;
 
 const PROMPT: &str = ">> ";
 
 struct ReplReader {
-	chars: VecDeque<u8>,
+	chars: VecDeque<char>,
+	history: Rc<RefCell<String>>,
 }
 
 impl Iterator for ReplReader {
@@ -22,55 +24,66 @@ impl Iterator for ReplReader {
 	fn next(&mut self) -> Option<Self::Item> {
 		match self.chars.pop_front() {
 			None => {
-				self.queue();
+				self.queue()?;
 				self.next()
 			}
-			Some(c) => Some(char::from(c)),
+			c => c,
 		}
 	}
 }
 
 impl ReplReader {
-	fn queue(&mut self) {
-		self.chars = self.get_line();
+	fn queue(&mut self) -> Option<()> {
+		self.chars = self.get_line()?;
+		Some(())
 	}
-	fn new() -> Self {
+	fn new(history: Rc<RefCell<String>>) -> Self {
 		ReplReader {
 			chars: VecDeque::new(),
+			history,
 		}
 	}
-	fn get_line(&mut self) -> VecDeque<u8> {
+	// none at the end of input or when stdin cannot be read, which ends the repl
+	fn get_line(&mut self) -> Option<VecDeque<char>> {
 		print!("{}", PROMPT);
-		io::stdout().flush().ok().expect("Could not flush");
+		io::stdout().flush().expect("Could not flush");
 		let s = &mut String::new();
 		match stdin().read_line(s) {
-			Err(_) => panic!(),
-			_ => (),
+			Ok(0) | Err(_) => return None,
+			Ok(_) => (),
 		}
-		s.clone().into_bytes().into()
+		self.history.borrow_mut().push_str(s);
+		Some(s.chars().collect())
 	}
 }
 
 pub fn start() {
 	let env = &mut Env::new(None);
-	let reader = ReplReader::new();
+	let history = Rc::new(RefCell::new(String::new()));
+	let reader = ReplReader::new(history.clone());
 	let lex = Lexer::new(reader);
 	let par = Parser::new(lex);
 
-	let mut eval = par.map(|n| match n {
-		Ok(o) => o.into_eval_node().eval(env),
-		Err(e) => Err(EvalError::ParserError(e)),
+	// only the value of a return is printed
+	let mut eval = par.filter_map(|n| match n {
+		Ok(o) => match eval_node(&o, env) {
+			Err(Unwind::Return(val)) => Some(Ok(val)),
+			Err(Unwind::Error(e)) => Some(Err(e)),
+			Ok(_) | Err(Unwind::Break | Unwind::Continue) => None,
+		},
+		Err(e) => Some(Err(EvalError::ParserError(e))),
 	});
 	loop {
-		let msg = match eval.find(|e| match e {
-			Ok(o) if o.get_type() == ObjType::ReturnValue => true,
-			Err(_) => true,
-			_ => false,
-		}) {
-			Some(Ok(o)) => o.inspect_obj(),
-			Some(Err(e)) => e.get_err_msg(),
-			None => panic!("Should not reach EOF in REPL"),
+		match eval.next() {
+			Some(Ok(o)) => println!("<< {}", o.inspect_obj()),
+			Some(Err(e)) => print!(
+				"{}",
+				Diagnostic::from(&e).render("<repl>", &history.borrow())
+			),
+			None => {
+				println!();
+				return;
+			}
 		};
-		print!("<< {}\n", msg);
 	}
 }
@@ -1,22 +1,35 @@
 #[derive(PartialEq, Eq, Debug, Hash, Clone)]
 pub enum TokenType {
 	Illegal,
+	Error,
 	Ident,
 	Int,
+	Float,
 	String,
+	Comment,
 
 	// Operators
 	Assign,
+	PlusAssign,
+	MinusAssign,
+	AsteriskAssign,
+	SlashAssign,
+	PercentAssign,
 	Plus,
 	Minus,
 	Bang,
 	Asterisk,
 	Slash,
+	Percent,
 
 	Lt,
 	Gt,
+	LtEq,
+	GtEq,
 	Eq,
 	Noteq,
+	And,
+	Or,
 
 	Colon,
 	Comma,
@@ -36,25 +49,43 @@ pub enum TokenType {
 	If,
 	Else,
 	Return,
+	While,
+	Break,
+	Continue,
+	For,
+	In,
 }
 
 impl TokenType {
 	pub fn get_name(&self) -> String {
 		match self {
 			TokenType::Illegal => "Illegal Token".to_string(),
+			TokenType::Error => "Error Token".to_string(),
 			TokenType::Ident => "Identifier Token".to_string(),
 			TokenType::Int => "Integer Token".to_string(),
+			TokenType::Float => "Float Token".to_string(),
 			TokenType::String => "String Token".to_string(),
+			TokenType::Comment => "Comment Token".to_string(),
 			TokenType::Assign => "Assign = Token".to_string(),
+			TokenType::PlusAssign => "+= Token".to_string(),
+			TokenType::MinusAssign => "-= Token".to_string(),
+			TokenType::AsteriskAssign => "*= Token".to_string(),
+			TokenType::SlashAssign => "/= Token".to_string(),
+			TokenType::PercentAssign => "%= Token".to_string(),
 			TokenType::Plus => "+ Token".to_string(),
 			TokenType::Minus => "- Token".to_string(),
 			TokenType::Bang => "! Token".to_string(),
 			TokenType::Asterisk => "* Token".to_string(),
 			TokenType::Slash => "/ Token".to_string(),
+			TokenType::Percent => "% Token".to_string(),
 			TokenType::Lt => "< Token".to_string(),
 			TokenType::Gt => "> Token".to_string(),
+			TokenType::LtEq => "<= Token".to_string(),
+			TokenType::GtEq => ">= Token".to_string(),
 			TokenType::Eq => "= Token".to_string(),
 			TokenType::Noteq => "!= Token".to_string(),
+			TokenType::And => "&& Token".to_string(),
+			TokenType::Or => "|| Token".to_string(),
 			TokenType::Colon => ": Token".to_string(),
 			TokenType::Comma => ", Token".to_string(),
 			TokenType::Semicolon => "; Token".to_string(),
@@ -69,6 +100,11 @@ impl TokenType {
 			TokenType::If => "If Token".to_string(),
 			TokenType::Else => "Else Token".to_string(),
 			TokenType::Return => "Return Token".to_string(),
+			TokenType::While => "While Token".to_string(),
+			TokenType::Break => "Break Token".to_string(),
+			TokenType::Continue => "Continue Token".to_string(),
+			TokenType::For => "For Token".to_string(),
+			TokenType::In => "In Token".to_string(),
 			TokenType::Lbracket => "Left Bracket Token".to_string(),
 			TokenType::Rbracket => "Right Bracket Token".to_string(),
 		}
@@ -77,16 +113,27 @@ impl TokenType {
 
 #[macro_export]
 macro_rules! token {
-	($tt: expr, $l:expr) => {{
+	($tt: expr, $l:expr, $span:expr) => {{
 		Token {
 			token_type: $tt,
 			literal: $l,
+			span: $span,
 		}
 	}};
 }
 
+// start and end are byte offsets into the source, line and column are 1-based
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, Default)]
+pub struct Span {
+	pub start: usize,
+	pub end: usize,
+	pub line: usize,
+	pub column: usize,
+}
+
 #[derive(PartialEq, Debug, Clone, Hash)]
 pub struct Token {
 	pub token_type: TokenType,
 	pub literal: String,
+	pub span: Span,
 }
@@ -0,0 +1,498 @@
+use crate::code::{read_u16, read_u8, Opcode, ASSIGN_OPERATORS};
+use crate::compiler::Bytecode;
+use crate::eval::builtins::BUILTINS;
+use crate::eval::*;
+use crate::object::*;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+const STACK_SIZE: usize = 2048;
+const MAX_FRAMES: usize = 1024;
+
+type ResultVm = Result<(), VmError>;
+
+#[derive(Debug)]
+pub enum VmError {
+	EvalError(EvalError),
+	StackOverflow(String),
+	InvalidBytecode(String),
+}
+
+impl VmError {
+	pub fn get_err_type(&self) -> String {
+		match self {
+			VmError::EvalError(e) => e.get_err_type(),
+			VmError::StackOverflow(_) => String::from("StackOverflow"),
+			VmError::InvalidBytecode(_) => String::from("InvalidBytecode"),
+		}
+	}
+	pub fn get_err_msg(&self) -> String {
+		match self {
+			VmError::EvalError(e) => e.get_err_msg(),
+			VmError::StackOverflow(m) => m.to_string(),
+			VmError::InvalidBytecode(m) => m.to_string(),
+		}
+	}
+}
+
+impl From<EvalError> for VmError {
+	fn from(e: EvalError) -> Self {
+		VmError::EvalError(e)
+	}
+}
+
+// locals live in the frame rather than on the stack so closures can keep them alive
+struct Frame {
+	closure: Rc<Closure>,
+	ip: usize,
+	base_pointer: usize,
+	locals: Vec<Slot>,
+	// the stack height when each loop being run was entered, which a break or continue
+	// goes back to so values left by the expressions around it are dropped
+	loops: Vec<usize>,
+}
+
+pub struct Vm {
+	constants: Vec<Value>,
+	stack: Vec<Value>,
+	globals: Vec<Value>,
+	frames: Vec<Frame>,
+}
+
+impl Vm {
+	pub fn new(bytecode: Bytecode) -> Self {
+		let main = Closure {
+			func: Rc::new(CompiledFunction {
+				instructions: bytecode.instructions,
+				lines: bytecode.lines,
+				num_locals: 0,
+				num_params: 0,
+				captures: Vec::new(),
+			}),
+			free: Vec::new(),
+		};
+		Vm {
+			constants: bytecode.constants,
+			stack: Vec::with_capacity(STACK_SIZE),
+			globals: Vec::new(),
+			frames: vec![Frame {
+				closure: Rc::new(main),
+				ip: 0,
+				base_pointer: 0,
+				locals: (0..bytecode.num_locals)
+					.map(|_| new_slot(Value::Null))
+					.collect(),
+				loops: Vec::new(),
+			}],
+		}
+	}
+
+	pub fn run(&mut self) -> Result<Value, VmError> {
+		loop {
+			let op = match self.read_opcode()? {
+				Some(op) => op,
+				None if self.frames.len() == 1 => return Ok(Value::Null),
+				None => {
+					return Err(VmError::InvalidBytecode(String::from(
+						"Function ended without returning",
+					)))
+				}
+			};
+
+			match op {
+				Opcode::Constant => {
+					let i = self.read_u16_operand()?;
+					let constant = self.constants.get(i).cloned().ok_or_else(|| {
+						VmError::InvalidBytecode(format!("Constant {} does not exist", i))
+					})?;
+					self.push(constant)?;
+				}
+				Opcode::Pop => {
+					self.pop()?;
+				}
+				Opcode::Add => self.execute_infix("+")?,
+				Opcode::Sub => self.execute_infix("-")?,
+				Opcode::Mul => self.execute_infix("*")?,
+				Opcode::Div => self.execute_infix("/")?,
+				Opcode::Mod => self.execute_infix("%")?,
+				Opcode::Equal => self.execute_infix("==")?,
+				Opcode::NotEqual => self.execute_infix("!=")?,
+				Opcode::GreaterThan => self.execute_infix(">")?,
+				Opcode::LessThan => self.execute_infix("<")?,
+				Opcode::GreaterEqual => self.execute_infix(">=")?,
+				Opcode::LessEqual => self.execute_infix("<=")?,
+				Opcode::True => self.push(Value::Boolean(true))?,
+				Opcode::False => self.push(Value::Boolean(false))?,
+				Opcode::Null => self.push(Value::Null)?,
+				Opcode::Minus => {
+					let right = self.pop()?;
+					self.push(minus_op(right)?)?;
+				}
+				Opcode::Bang => {
+					let right = self.pop()?;
+					self.push(bang_op(&right))?;
+				}
+				Opcode::Jump => {
+					let target = self.read_u16_operand()?;
+					self.current_frame().ip = target;
+				}
+				Opcode::JumpNotTruthy => {
+					let target = self.read_u16_operand()?;
+					let condition = self.pop()?;
+					if !condition.is_truthy() {
+						self.current_frame().ip = target;
+					}
+				}
+				Opcode::JumpNotTruthyOrPop | Opcode::JumpTruthyOrPop => {
+					let target = self.read_u16_operand()?;
+					let operator = match op {
+						Opcode::JumpTruthyOrPop => "||",
+						_ => "&&",
+					};
+					let left = self.stack.last().ok_or_else(stack_underflow)?;
+					match logical_decides(operator, left)? {
+						true => self.current_frame().ip = target,
+						false => {
+							self.pop()?;
+						}
+					}
+				}
+				Opcode::GetGlobal => {
+					let i = self.read_u16_operand()?;
+					let global = self.globals.get(i).cloned().ok_or_else(|| {
+						VmError::InvalidBytecode(format!("Global {} is not set", i))
+					})?;
+					self.push(global)?;
+				}
+				Opcode::SetGlobal => {
+					let i = self.read_u16_operand()?;
+					let val = self.pop()?;
+					if i >= self.globals.len() {
+						self.globals.resize_with(i + 1, || Value::Null);
+					}
+					self.globals[i] = val;
+				}
+				Opcode::GetLocal => {
+					let i = self.read_u8_operand()?;
+					let local = self.current_frame().locals.get(i).map(|l| l.borrow().clone());
+					self.push(local.ok_or_else(|| missing_local(i))?)?;
+				}
+				Opcode::SetLocal => {
+					let i = self.read_u8_operand()?;
+					let val = self.pop()?;
+					let local = self.current_frame().locals.get(i);
+					*local.ok_or_else(|| missing_local(i))?.borrow_mut() = val;
+				}
+				Opcode::FreshLocal => {
+					let i = self.read_u8_operand()?;
+					let local = self.current_frame().locals.get_mut(i);
+					*local.ok_or_else(|| missing_local(i))? = new_slot(Value::Null);
+				}
+				Opcode::GetBuiltin => {
+					let i = self.read_u8_operand()?;
+					let (_, func) = BUILTINS.get(i).ok_or_else(|| {
+						VmError::InvalidBytecode(format!("Builtin {} does not exist", i))
+					})?;
+					self.push(Value::Builtin(*func))?;
+				}
+				Opcode::Array => {
+					let len = self.read_u16_operand()?;
+					let mems = self.pop_n(len)?.into_iter().collect::<VecDeque<_>>();
+					self.push(Value::Array(Array::new(mems)))?;
+				}
+				Opcode::Hash => {
+					let len = self.read_u16_operand()?;
+					let mut elements = self.pop_n(len)?.into_iter();
+					let mut pairs = Vec::with_capacity(len / 2);
+					while let (Some(k), Some(v)) = (elements.next(), elements.next()) {
+						pairs.push(HashPair(k, v));
+					}
+					self.push(build_hash(pairs)?)?;
+				}
+				Opcode::Index => {
+					let index = self.pop()?;
+					let left = self.pop()?;
+					self.push(eval_index(left, index)?)?;
+				}
+				Opcode::SetIndex => {
+					let op = self.read_u8_operand()?;
+					let operator = ASSIGN_OPERATORS.get(op).ok_or_else(|| {
+						VmError::InvalidBytecode(format!("Unknown assignment operator {}", op))
+					})?;
+					let value = self.pop()?;
+					let index = self.pop()?;
+					let left = self.pop()?;
+					self.push(eval_index_assign(operator, left, index, value)?)?;
+				}
+				Opcode::Call => {
+					let num_args = self.read_u8_operand()?;
+					self.call(num_args)?;
+				}
+				Opcode::ReturnValue => {
+					let val = self.pop()?;
+					if self.frames.len() == 1 {
+						return Ok(val);
+					}
+					self.return_from_frame(val)?;
+				}
+				Opcode::Return => {
+					if self.frames.len() == 1 {
+						return Ok(Value::Null);
+					}
+					self.return_from_frame(Value::Null)?;
+				}
+				Opcode::Closure => {
+					let i = self.read_u16_operand()?;
+					let num_free = self.read_u8_operand()?;
+					self.push_closure(i, num_free)?;
+				}
+				Opcode::GetFree => {
+					let i = self.read_u8_operand()?;
+					let free = self
+						.current_frame()
+						.closure
+						.free
+						.get(i)
+						.map(|f| f.borrow().clone());
+					self.push(free.ok_or_else(|| missing_free(i))?)?;
+				}
+				Opcode::SetFree => {
+					let i = self.read_u8_operand()?;
+					let val = self.pop()?;
+					let free = self.current_frame().closure.free.get(i);
+					*free.ok_or_else(|| missing_free(i))?.borrow_mut() = val;
+				}
+				Opcode::Iter => {
+					let iterable = self.pop()?;
+					self.push(Value::Iterator(Rc::new(iterate(&iterable)?)))?;
+				}
+				Opcode::IterNext => {
+					let target = self.read_u16_operand()?;
+					let vars = self.read_u8_operand()?;
+					let values = match self.stack.last() {
+						Some(Value::Iterator(iter)) => iter.next(vars),
+						_ => {
+							return Err(VmError::InvalidBytecode(String::from(
+								"IterNext without an iterator",
+							)))
+						}
+					};
+					match values {
+						Some(values) => values.into_iter().try_for_each(|v| self.push(v))?,
+						None => self.current_frame().ip = target,
+					}
+				}
+				Opcode::EnterLoop => {
+					let height = self.stack.len();
+					self.current_frame().loops.push(height);
+				}
+				Opcode::LeaveLoop => {
+					self.current_frame().loops.pop().ok_or_else(outside_loop)?;
+				}
+				Opcode::LoopJump => {
+					let target = self.read_u16_operand()?;
+					let height = *self.current_frame().loops.last().ok_or_else(outside_loop)?;
+					self.stack.truncate(height);
+					self.current_frame().ip = target;
+				}
+			}
+		}
+	}
+
+	// source line of the instruction being executed, when the bytecode has debug info
+	pub fn current_line(&self) -> Option<usize> {
+		let frame = self.frames.last()?;
+		frame
+			.closure
+			.func
+			.lines
+			.iter()
+			.take_while(|(pos, _)| *pos < frame.ip)
+			.last()
+			.map(|(_, line)| *line)
+	}
+
+	fn current_frame(&mut self) -> &mut Frame {
+		self.frames.last_mut().expect("vm always has a frame")
+	}
+
+	fn read_opcode(&mut self) -> Result<Option<Opcode>, VmError> {
+		let frame = self.current_frame();
+		let byte = match frame.closure.func.instructions.0.get(frame.ip) {
+			Some(b) => *b,
+			None => return Ok(None),
+		};
+		frame.ip += 1;
+		Opcode::from_byte(byte)
+			.map(Some)
+			.ok_or_else(|| VmError::InvalidBytecode(format!("Unknown opcode {}", byte)))
+	}
+
+	fn read_u16_operand(&mut self) -> Result<usize, VmError> {
+		let frame = self.current_frame();
+		let operand = frame
+			.closure
+			.func
+			.instructions
+			.0
+			.get(frame.ip..frame.ip + 2)
+			.map(read_u16)
+			.ok_or_else(truncated_instruction)?;
+		frame.ip += 2;
+		Ok(operand as usize)
+	}
+
+	fn read_u8_operand(&mut self) -> Result<usize, VmError> {
+		let frame = self.current_frame();
+		let operand = frame
+			.closure
+			.func
+			.instructions
+			.0
+			.get(frame.ip..frame.ip + 1)
+			.map(read_u8)
+			.ok_or_else(truncated_instruction)?;
+		frame.ip += 1;
+		Ok(operand as usize)
+	}
+
+	fn push(&mut self, obj: Value) -> ResultVm {
+		if self.stack.len() >= STACK_SIZE {
+			return Err(VmError::StackOverflow(String::from("Stack overflow")));
+		}
+		self.stack.push(obj);
+		Ok(())
+	}
+
+	fn pop(&mut self) -> Result<Value, VmError> {
+		self.stack.pop().ok_or_else(stack_underflow)
+	}
+
+	fn pop_n(&mut self, n: usize) -> Result<Vec<Value>, VmError> {
+		let start = self
+			.stack
+			.len()
+			.checked_sub(n)
+			.ok_or_else(stack_underflow)?;
+		Ok(self.stack.split_off(start))
+	}
+
+	fn execute_infix(&mut self, operator: &str) -> ResultVm {
+		let right = self.pop()?;
+		let left = self.pop()?;
+		self.push(eval_infix(operator, left, right)?)
+	}
+
+	fn call(&mut self, num_args: usize) -> ResultVm {
+		let callee_pos = self
+			.stack
+			.len()
+			.checked_sub(num_args + 1)
+			.ok_or_else(stack_underflow)?;
+		match &self.stack[callee_pos] {
+			Value::Closure(closure) => {
+				let closure = closure.clone();
+				self.call_closure(closure, num_args)
+			}
+			Value::Builtin(func) => {
+				let func = *func;
+				let args = self.pop_n(num_args)?;
+				self.pop()?;
+				self.push(func(args)?)
+			}
+			_ => Err(VmError::EvalError(EvalError::UnexpectedNode(String::from(
+				"Expected Function Identifier",
+			)))),
+		}
+	}
+
+	fn call_closure(&mut self, closure: Rc<Closure>, num_args: usize) -> ResultVm {
+		let func = &closure.func;
+		if func.num_params != num_args {
+			return Err(VmError::EvalError(EvalError::IncorrectArgs(format!(
+				"Function expects {} args, got {}",
+				func.num_params, num_args
+			))));
+		}
+		if self.frames.len() >= MAX_FRAMES {
+			return Err(VmError::StackOverflow(String::from(
+				"Maximum call depth exceeded",
+			)));
+		}
+		let mut locals = self.pop_n(num_args)?;
+		locals.resize_with(func.num_locals, || Value::Null);
+		self.frames.push(Frame {
+			closure,
+			ip: 0,
+			base_pointer: self.stack.len(),
+			locals: locals.into_iter().map(new_slot).collect(),
+			loops: Vec::new(),
+		});
+		Ok(())
+	}
+
+	fn push_closure(&mut self, i: usize, num_free: usize) -> ResultVm {
+		let func = match self.constants.get(i) {
+			Some(Value::CompiledFunction(func)) => func.clone(),
+			_ => {
+				return Err(VmError::InvalidBytecode(format!(
+					"Constant {} is not a function",
+					i
+				)))
+			}
+		};
+		if func.captures.len() != num_free {
+			return Err(VmError::InvalidBytecode(format!(
+				"Function {} captures {} free variables, not {}",
+				i,
+				func.captures.len(),
+				num_free
+			)));
+		}
+		let frame = self.current_frame();
+		let free = func
+			.captures
+			.iter()
+			.map(|c| match *c {
+				Capture::Local(index) => frame
+					.locals
+					.get(index)
+					.cloned()
+					.ok_or_else(|| missing_local(index)),
+				Capture::Free(index) => frame
+					.closure
+					.free
+					.get(index)
+					.cloned()
+					.ok_or_else(|| missing_free(index)),
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		self.push(Value::Closure(Rc::new(Closure { func, free })))
+	}
+
+	fn return_from_frame(&mut self, val: Value) -> ResultVm {
+		let frame = self.frames.pop().expect("vm always has a frame");
+		self.stack.truncate(frame.base_pointer - 1);
+		self.push(val)
+	}
+}
+
+fn stack_underflow() -> VmError {
+	VmError::InvalidBytecode(String::from("Stack underflow"))
+}
+
+fn missing_local(i: usize) -> VmError {
+	VmError::InvalidBytecode(format!("Local {} does not exist", i))
+}
+
+fn missing_free(i: usize) -> VmError {
+	VmError::InvalidBytecode(format!("Free variable {} does not exist", i))
+}
+
+fn outside_loop() -> VmError {
+	VmError::InvalidBytecode(String::from("Loop instruction outside of a loop"))
+}
+
+fn truncated_instruction() -> VmError {
+	VmError::InvalidBytecode(String::from("Instruction is missing operands"))
+}
@@ -0,0 +1,430 @@
+mod common;
+
+use common::{check, check_error};
+use monkey_compiler::eval::{eval_node, EvalError, Unwind};
+use monkey_compiler::object::{Env, Value};
+
+#[test]
+fn arithmetic_and_prefix() {
+	check(&[
+		("return 1 + 2 * 3;", "7"),
+		("return (1 + 2) * 3;", "9"),
+		("return 10 - 4 / 2;", "8"),
+		("return -5 + 2;", "-3"),
+		("return !true;", "false"),
+		("return !!5;", "true"),
+	]);
+}
+
+#[test]
+fn comparisons_and_conditionals() {
+	check(&[
+		("return 1 < 2;", "true"),
+		("return 1 > 2;", "false"),
+		("return 1 == 1;", "true"),
+		("return true != false;", "true"),
+		("if (1 < 2) { return 10; } else { return 20; };", "10"),
+		("if (1 > 2) { return 10; } else { return 20; };", "20"),
+		("if (false) { return 10; }; return 30;", "30"),
+		("return if (1 < 2) { 10; } else { 20; };", "10"),
+		("return if (1 > 2) { 10; } else { 20; };", "20"),
+		("return if (false) { 10; };", "null"),
+		("return if (true) { let a = 1; };", "null"),
+	]);
+}
+
+#[test]
+fn bindings_strings_and_collections() {
+	check(&[
+		("let a = 5; let b = a * 2; return a + b;", "15"),
+		("return \"mon\" + \"key\";", "monkey"),
+		("return [1, 2 + 3, 4][1];", "5"),
+		("return {\"a\": 1, \"b\": 2}[\"b\"];", "2"),
+		("return {1: true}[1];", "true"),
+		("return len([1, 2, 3]) + len(\"ab\");", "5"),
+		("return first(rest(push([1, 2], 3)));", "2"),
+		(
+			"let log = []; let f = fn(x) { log = push(log, x); x; }; [f(1)][f(0)]; return log;",
+			"[, 1, 0]",
+		),
+	]);
+}
+
+#[test]
+fn functions_and_calls() {
+	check(&[
+		(
+			"let add = fn(a, b) { return a + b; }; return add(2, add(3, 4));",
+			"9",
+		),
+		("let f = fn() { return 1; 2; }; return f();", "1"),
+		("let f = fn() { }; return f();", "null"),
+		(
+			"let twice = fn(g, x) { return g(g(x)); }; return twice(fn(x) { return x * 3; }, 2);",
+			"18",
+		),
+	]);
+}
+
+#[test]
+fn implicit_returns() {
+	check(&[
+		("let f = fn() { 5; }; return f();", "5"),
+		("let f = fn(x) { let y = x * 2; y + 1; }; return f(3);", "7"),
+		("let f = fn() { let a = 1; }; return f();", "null"),
+		(
+			"let max = fn(a, b) { if (a > b) { a; } else { b; }; }; return max(3, 8);",
+			"8",
+		),
+		("let f = fn() { return 1; 2; }; return f();", "1"),
+		(
+			"let f = fn() { let x = if (true) { return 5; } else { 0; }; 10; }; return f();",
+			"5",
+		),
+		(
+			"let f = fn(n) { [1, if (n > 0) { return n * 2; } else { 0; }]; }; return [f(0), f(4)];",
+			"[, [, 1, 0], 8]",
+		),
+	]);
+}
+
+#[test]
+fn wrong_argument_counts() {
+	check(&[
+		(
+			"let f = fn(a, b) { a; }; return f(1);",
+			"IncorrectArgs: Function expects 2 args, got 1",
+		),
+		(
+			"let f = fn() { 1; }; return f(1, 2);",
+			"IncorrectArgs: Function expects 0 args, got 2",
+		),
+	]);
+	check_error(&[("let f = fn(a) { a; }; return f();", "IncorrectArgs")]);
+}
+
+#[test]
+fn comments_are_ignored() {
+	check(&[
+		("let a = 1; // the rest is ignored\nreturn a;", "1"),
+		(
+			"let a = /* inline */ 2; return a * /* nested /* twice */ */ 3;",
+			"6",
+		),
+		("// only a comment", "null"),
+	]);
+}
+
+#[test]
+fn string_escapes() {
+	check(&[
+		(r#"return len("a\tb\n");"#, "4"),
+		(r#"return "say \"hi\"";"#, "say \"hi\""),
+		(r#"return "caf\u{e9}" == "café";"#, "true"),
+	]);
+	check_error(&[(r#"let s = "open;"#, "ParserError")]);
+}
+
+#[test]
+fn floats_and_promotion() {
+	check(&[
+		("return 3.14;", "3.14"),
+		("return 1.5e3;", "1500.0"),
+		("return 1e-9;", "1e-9"),
+		("return -0.5;", "-0.5"),
+		("return 1 + 2.5;", "3.5"),
+		("return 7 / 2;", "3"),
+		("return 7.0 / 2;", "3.5"),
+		("return 5.5 % 2;", "1.5"),
+		("return 1.0 / 0;", "inf"),
+		("return 1 == 1.0;", "true"),
+		("return 2.5 > 2;", "true"),
+		("return 0.1 + 0.2;", "0.30000000000000004"),
+	]);
+}
+
+#[test]
+fn big_integers() {
+	check(&[
+		("return 9223372036854775807 + 1;", "9223372036854775808"),
+		("return -9223372036854775807 - 2;", "-9223372036854775809"),
+		(
+			"return 9223372036854775807 * 9223372036854775807;",
+			"85070591730234615847396907784232501249",
+		),
+		("return (9223372036854775807 + 1) - 1;", "9223372036854775807"),
+		("return (9223372036854775807 + 1) / 2 == 4611686018427387904;", "true"),
+		(
+			"let big = 9223372036854775807 * 4; let h = {big: 1, 2: 2}; return [h[big], h[9223372036854775807 * 4], h[2]];",
+			"[, 1, 1, 2]",
+		),
+	]);
+	check_error(&[
+		("return 9223372036854775808;", "ParserError"),
+		(
+			"let a = 9223372036854775807 * 3; let b = 9223372036854775807 * 5; return {a: 1}[b];",
+			"OutOfBounds",
+		),
+	]);
+}
+
+#[test]
+fn division_by_zero_and_overflowing_division() {
+	check(&[
+		("return 1 / 0;", "DivisionByZero: Division by zero in 1 / 0"),
+		("return 1 % 0;", "DivisionByZero: Division by zero in 1 % 0"),
+		(
+			"return (9223372036854775807 * 2) / 0;",
+			"DivisionByZero: Division by zero in 18446744073709551614 / 0",
+		),
+		(
+			"return (-9223372036854775807 - 1) / -1;",
+			"9223372036854775808",
+		),
+		("return (-9223372036854775807 - 1) % -1;", "0"),
+		("return -7 % 3;", "-1"),
+		("return 7 % -3;", "1"),
+	]);
+}
+
+#[test]
+fn division_by_zero_keeps_its_operands() {
+	let program = common::parse("let a = 5; a % (a - 5);");
+	match eval_node(&program, &mut Env::new(None)) {
+		Err(Unwind::Error(EvalError::Located(e, _))) => match *e {
+			EvalError::DivisionByZero {
+				operator,
+				left,
+				right,
+			} => {
+				assert_eq!(operator, "%");
+				assert!(matches!(
+					(left, right),
+					(Value::Integer(5), Value::Integer(0))
+				));
+			}
+			e => panic!("expected an arithmetic error, got {:?}", e),
+		},
+		other => panic!("expected an arithmetic error, got {:?}", other),
+	}
+}
+
+#[test]
+fn closures_share_their_scope() {
+	check(&[
+		(
+			"let counter = fn() { let n = 0; fn() { n += 1; n; }; }; let c = counter(); c(); c(); return c();",
+			"3",
+		),
+		(
+			"let make = fn() { let n = 0; [fn() { n += 1; }, fn() { n; }]; }; let p = make(); p[0](); p[0](); return p[1]();",
+			"2",
+		),
+		("let x = 1; let f = fn() { x; }; x = 5; return f();", "5"),
+		(
+			"let fact = fn(n) { if (n < 2) { 1; } else { n * fact(n - 1); }; }; return fact(20);",
+			"2432902008176640000",
+		),
+		(
+			"let even = fn(n) { if (n == 0) { true; } else { odd(n - 1); }; }; let odd = fn(n) { if (n == 0) { false; } else { even(n - 1); }; }; return even(10);",
+			"true",
+		),
+		("let f = fn() { x; }; let x = 3; return f();", "3"),
+		(
+			"let f = fn() { let g = fn() { y; }; let y = 4; g(); }; return f();",
+			"4",
+		),
+		(
+			"let fs = []; for (i in range(2)) { let f = fn() { v; }; let v = i * 10; fs = push(fs, f); }; return [fs[0](), fs[1]()];",
+			"[, 0, 10]",
+		),
+	]);
+}
+
+#[test]
+fn assignment() {
+	check(&[
+		("let a = 1; a = a + 1; return a;", "2"),
+		("let a = 1; return a = 7;", "7"),
+		("let s = 10; s -= 3; s *= 2; s /= 7; s %= 3; return s;", "2"),
+		("let s = \"a\"; s += \"b\"; return s;", "ab"),
+		(
+			"let a = 1; let f = fn() { a = 2; let a = 3; a = 4; }; f(); return a;",
+			"2",
+		),
+	]);
+	check_error(&[("y = 1;", "Undefined"), ("5 = 1;", "ParserError")]);
+}
+
+#[test]
+fn index_assignment() {
+	check(&[
+		("let a = [1, 2]; a[1] = 5; return a;", "[, 1, 5]"),
+		("let a = [1, 2]; let b = a; b[0] = 9; return a[0];", "9"),
+		("let h = {\"k\": 1}; h[\"k\"] += 4; return h[\"k\"];", "5"),
+		("let h = {}; h[\"new\"] = 3; return h[\"new\"];", "3"),
+		(
+			"let a = [1]; let f = fn(x) { x[0] = 2; }; f(a); return a[0];",
+			"2",
+		),
+		("let a = [[1]]; a[0][0] = 8; return a[0][0];", "8"),
+	]);
+	check_error(&[
+		("let a = [1]; a[5] = 1;", "OutOfBounds"),
+		("let a = [1]; a[0] = a;", "InvalidAssignment"),
+	]);
+}
+
+#[test]
+fn while_loops() {
+	check(&[
+		(
+			"let i = 0; let sum = 0; while (i < 10) { sum += i; i += 1; }; return sum;",
+			"45",
+		),
+		(
+			"let i = 0; while (true) { i += 1; if (i == 5) { break; }; }; return i;",
+			"5",
+		),
+		(
+			"let i = 0; let odd = 0; while (i < 10) { i += 1; if (i % 2 == 0) { continue; }; odd += 1; }; return odd;",
+			"5",
+		),
+		(
+			"let f = fn() { let i = 0; while (true) { i += 1; if (i > 3) { return i * 10; }; }; }; return f();",
+			"40",
+		),
+		(
+			"let i = 0; let n = 0; while (i < 3) { let j = 0; while (true) { j += 1; n += 1; if (j == 2) { break; }; }; i += 1; }; return n;",
+			"6",
+		),
+		(
+			"let i = 0; while (i < 3000) { i += 1; let a = [1, if (true) { continue; } else { 0; }]; }; return i;",
+			"3000",
+		),
+		(
+			"let i = 0; while (i < 5) { let x = if (i == 2) { break; } else { i; }; i += 1; }; return i;",
+			"2",
+		),
+		(
+			"let i = 0; while (true) { let a = [1, 2, if (i > 2) { break; } else { 0; }]; i += 1; }; return i;",
+			"3",
+		),
+	]);
+	check_error(&[
+		("let r = while (false) { 1; };", "ParserError"),
+		("break;", "ParserError"),
+		("let f = fn() { continue; };", "ParserError"),
+	]);
+}
+
+#[test]
+fn for_in_loops() {
+	check(&[
+		(
+			"let sum = 0; for (x in [1, 2, 3]) { sum += x; }; return sum;",
+			"6",
+		),
+		(
+			"let out = \"\"; for (i, c in \"héllo\") { if (i == 1) { out += c; }; }; return out;",
+			"é",
+		),
+		("let n = 0; for (c in \"日本語\") { n += 1; }; return [n, len(\"日本語\")];", "[, 3, 3]"),
+		(
+			"let sum = 0; for (k, v in {\"a\": 1, \"b\": 2}) { sum += v; }; return sum;",
+			"3",
+		),
+		("let n = 0; for (k in {\"a\": 1, \"b\": 2}) { n += len(k); }; return n;", "2"),
+		("let sum = 0; for (i in range(2, 5)) { sum += i; }; return sum;", "9"),
+		(
+			"let sum = 0; for (i in range(10)) { if (i == 6) { break; }; if (i % 2 == 0) { continue; }; sum += i; }; return sum;",
+			"9",
+		),
+		(
+			"let a = [1, 2]; let n = 0; for (x in a) { if (n == 0) { a[1] = 5; }; n += x; }; return n;",
+			"6",
+		),
+		(
+			"let fs = []; for (i in range(3)) { fs = push(fs, fn() { i; }); }; return fs[0]() + fs[2]();",
+			"2",
+		),
+		(
+			"let n = 0; for (i in range(3)) { for (j in [10]) { let a = [0, if (true) { break; } else { 0; }]; }; n += 1; }; return n;",
+			"3",
+		),
+		(
+			"let n = 0; for (i in range(3000)) { n += 1; let a = [1, if (true) { continue; } else { 0; }]; }; return n;",
+			"3000",
+		),
+	]);
+	check_error(&[("for (x in 5) { x; };", "UnexpectedNode")]);
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+	check(&[
+		("return true && false;", "false"),
+		("return false || 5;", "5"),
+		("return 0 && 1;", "0"),
+		("return \"\" || \"x\";", "x"),
+		("return 1 < 2 && 2 < 3;", "true"),
+		("return false && 1 / 0;", "false"),
+		(
+			"let n = 0; let bump = fn() { n += 1; true; }; false && bump(); true || bump(); true && bump(); return n;",
+			"1",
+		),
+		("return true || false && false;", "true"),
+	]);
+}
+
+#[test]
+fn comparison_operators() {
+	check(&[
+		("return [1 <= 1, 1 <= 0, 2 >= 2, 1 >= 2];", "[, true, false, true, false]"),
+		("return [1.5 <= 2, 3 >= 2.5];", "[, true, true]"),
+		("return [\"apple\" < \"banana\", \"b\" >= \"abc\", \"a\" <= \"a\"];", "[, true, true, true]"),
+		(
+			"return [9223372036854775807 * 2 > 9223372036854775807, -9223372036854775807 * 2 <= 0];",
+			"[, true, true]",
+		),
+		("return [[1, [2]] == [1, [2]], [1] != [1, 2]];", "[, true, true]"),
+		("return {\"a\": 1, \"b\": 2} == {\"b\": 2, \"a\": 1};", "true"),
+		("return [1 == 1.0, 1 == \"1\", true != 1];", "[, true, false, true]"),
+	]);
+	check_error(&[
+		("return \"a\" < 1;", "UnexpectedNode"),
+		("let f = fn() { 1; }; return f == f;", "UnexpectedNode"),
+	]);
+}
+
+#[test]
+fn else_if_chains() {
+	let grade = "let grade = fn(n) { if (n >= 90) { \"a\"; } else if (n >= 80) { \"b\"; } else if (n >= 70) { \"c\"; } else { \"f\"; }; };";
+	check(&[
+		(&format!("{} return grade(95);", grade), "a"),
+		(&format!("{} return grade(85);", grade), "b"),
+		(&format!("{} return grade(70);", grade), "c"),
+		(&format!("{} return grade(10);", grade), "f"),
+		("return if (false) { 1; } else if (false) { 2; };", "null"),
+		(
+			"let n = 0; if (true) { n += 1; } else if (true) { n += 10; }; return n;",
+			"1",
+		),
+	]);
+}
+
+#[test]
+fn truthiness() {
+	check(&[
+		(
+			"let t = fn(x) { if (x) { true; } else { false; }; }; return [t(0), t(0.0), t(\"\"), t([]), t({}), t(range(0)), t(if (false) { 1; })];",
+			"[, false, false, false, false, false, false, false]",
+		),
+		(
+			"let t = fn(x) { if (x) { true; } else { false; }; }; return [t(1), t(-0.5), t(\"false\"), t([0]), t({0: 0}), t(range(1)), t(fn() { 1; })];",
+			"[, true, true, true, true, true, true, true]",
+		),
+		("return [!0, !\"\", !\"x\", !![]];", "[, true, true, false, false]"),
+		("let i = 3; let n = 0; while (i) { i -= 1; n += 1; }; return n;", "3"),
+	]);
+}
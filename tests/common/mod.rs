@@ -0,0 +1,59 @@
+#![allow(dead_code)]
+
+use monkey_compiler::ast::Program;
+use monkey_compiler::compiler::{Bytecode, Compiler};
+use monkey_compiler::eval::{eval_node, Unwind};
+use monkey_compiler::lexer::Lexer;
+use monkey_compiler::object::Env;
+use monkey_compiler::parser::Parser;
+use monkey_compiler::vm::Vm;
+
+pub fn parse(src: &str) -> Program {
+	Parser::new(Lexer::new(src.chars())).parse_program()
+}
+
+pub fn compile(src: &str) -> Bytecode {
+	let mut compiler = Compiler::new();
+	if let Err(errors) = compiler.compile(parse(src)) {
+		panic!("{}: {}", src, errors[0].get_err_msg());
+	}
+	compiler.bytecode()
+}
+
+// a program's result is the value of its top level return, errors become "Type: message"
+pub fn eval(src: &str) -> String {
+	match eval_node(&parse(src), &mut Env::new(None)) {
+		Ok(v) => v.inspect_obj(),
+		Err(Unwind::Error(e)) => format!("{}: {}", e.get_err_type(), e.get_err_msg()),
+		Err(flow) => panic!("{:?} escaped the program", flow),
+	}
+}
+
+pub fn run_vm(src: &str) -> String {
+	let mut compiler = Compiler::new();
+	if let Err(errors) = compiler.compile(parse(src)) {
+		return format!("{}: {}", errors[0].get_err_type(), errors[0].get_err_msg());
+	}
+	match Vm::new(compiler.bytecode()).run() {
+		Ok(v) => v.inspect_obj(),
+		Err(e) => format!("{}: {}", e.get_err_type(), e.get_err_msg()),
+	}
+}
+
+// every case has to give the expected result in the tree-walker and in the vm
+pub fn check(cases: &[(&str, &str)]) {
+	for (src, expected) in cases {
+		assert_eq!(eval(src), *expected, "tree-walker: {}", src);
+		assert_eq!(run_vm(src), *expected, "vm: {}", src);
+	}
+}
+
+// for errors whose wording differs between the two, only the kind has to match
+pub fn check_error(cases: &[(&str, &str)]) {
+	for (src, kind) in cases {
+		let prefix = format!("{}: ", kind);
+		let (e, v) = (eval(src), run_vm(src));
+		assert!(e.starts_with(&prefix), "tree-walker: {} gave {}", src, e);
+		assert!(v.starts_with(&prefix), "vm: {} gave {}", src, v);
+	}
+}
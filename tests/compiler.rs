@@ -0,0 +1,69 @@
+mod common;
+
+use common::compile;
+use monkey_compiler::code::{make, Opcode};
+use monkey_compiler::compiler::Compiler;
+
+fn instructions(ops: &[(Opcode, &[usize])]) -> Vec<u8> {
+	ops.iter()
+		.flat_map(|(op, operands)| make(*op, operands))
+		.collect()
+}
+
+#[test]
+fn integer_arithmetic_uses_constants() {
+	let bytecode = compile("1 + 2;");
+	assert_eq!(
+		bytecode.instructions.0,
+		instructions(&[
+			(Opcode::Constant, &[0]),
+			(Opcode::Constant, &[1]),
+			(Opcode::Add, &[]),
+			(Opcode::Pop, &[]),
+		])
+	);
+	let constants: Vec<String> = bytecode.constants.iter().map(|c| c.inspect_obj()).collect();
+	assert_eq!(constants, ["1", "2"]);
+}
+
+#[test]
+fn globals_are_set_and_read_by_index() {
+	let bytecode = compile("let a = 1; let b = a; b;");
+	assert_eq!(
+		bytecode.instructions.0,
+		instructions(&[
+			(Opcode::Constant, &[0]),
+			(Opcode::SetGlobal, &[0]),
+			(Opcode::GetGlobal, &[0]),
+			(Opcode::SetGlobal, &[1]),
+			(Opcode::GetGlobal, &[1]),
+			(Opcode::Pop, &[]),
+		])
+	);
+}
+
+#[test]
+fn conditionals_jump_over_branches() {
+	let bytecode = compile("if (true) { 10; }; 3333;");
+	assert_eq!(
+		bytecode.instructions.0,
+		instructions(&[
+			(Opcode::True, &[]),
+			(Opcode::JumpNotTruthy, &[10]),
+			(Opcode::Constant, &[0]),
+			(Opcode::Jump, &[11]),
+			(Opcode::Null, &[]),
+			(Opcode::Pop, &[]),
+			(Opcode::Constant, &[1]),
+			(Opcode::Pop, &[]),
+		])
+	);
+}
+
+#[test]
+fn undefined_identifiers_are_compile_errors() {
+	let mut compiler = Compiler::new();
+	let errors = compiler.compile(common::parse("x; y;")).unwrap_err();
+	let kinds: Vec<String> = errors.iter().map(|e| e.get_err_type()).collect();
+	assert_eq!(kinds, ["Undefined", "Undefined"]);
+}
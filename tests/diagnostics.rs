@@ -0,0 +1,60 @@
+mod common;
+
+use monkey_compiler::diagnostics::{line_span, Diagnostic};
+
+#[test]
+fn parser_errors_underline_the_token() {
+	let src = "let a = 1;\nlet = 2;\n";
+	let program = common::parse(src);
+	let report = Diagnostic::from(&program.errors[0]).render("test.mk", src);
+	assert_eq!(
+		report,
+		"\
+error[ParserError]: Unexpected =, expected Identifier Token
+ --> test.mk:2:5
+  |
+2 | let = 2;
+  |     ^
+"
+	);
+}
+
+#[test]
+fn notes_and_help_follow_the_snippet() {
+	let src = "\tlet x = \"héllo\" + 1;";
+	let span = line_span(src, 1);
+	let report = Diagnostic::new(String::from("Kind"), String::from("message"))
+		.with_span(span)
+		.with_note("a note")
+		.with_help("some help")
+		.render("test.mk", src);
+	assert_eq!(
+		report,
+		"\
+error[Kind]: message
+ --> test.mk:1:2
+  |
+1 | \tlet x = \"héllo\" + 1;
+  | \t^^^^^^^^^^^^^^^^^^^^
+  = note: a note
+  = help: some help
+"
+	);
+}
+
+#[test]
+fn diagnostics_without_a_span_have_no_snippet() {
+	let report = Diagnostic::new(String::from("Kind"), String::from("message"))
+		.with_note("a note")
+		.render("test.mk", "let a = 1;");
+	assert_eq!(report, "error[Kind]: message\n = note: a note\n");
+}
+
+#[test]
+fn line_spans_skip_indentation() {
+	let src = "let a = 1;\n    a + 2;  \n";
+	let span = line_span(src, 2).unwrap();
+	assert_eq!((span.line, span.column), (2, 5));
+	assert_eq!(&src[span.start..span.end], "a + 2;");
+	assert!(line_span(src, 5).is_none());
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::compile;
+use monkey_compiler::disassembler::disassemble;
+use monkey_compiler::module::{DebugInfo, Module};
+
+const SOURCE: &str = "let a = 1;
+let f = fn(x) {
+  x + a;
+};
+return f(2);
+";
+
+fn module(debug: bool) -> Module {
+	Module {
+		bytecode: compile(SOURCE),
+		debug: debug.then(|| DebugInfo {
+			source_name: String::from("test.mk"),
+			source: String::from(SOURCE),
+		}),
+	}
+}
+
+#[test]
+fn listing_is_annotated_with_source_lines() {
+	let expected = "\
+== main: test.mk ==
+   1 | let a = 1;
+0000 OpConstant       0      (1)
+0003 OpSetGlobal      0
+   2 | let f = fn(x) {
+0006 OpClosure        1 0    (<function>, 0 free)
+0010 OpSetGlobal      1
+   5 | return f(2);
+0013 OpGetGlobal      1
+0016 OpConstant       2      (2)
+0019 OpCall           1
+0021 OpReturnValue
+
+== constant 1: function (1 params, 1 locals) ==
+   3 | x + a;
+0000 OpGetLocal       0
+0002 OpGetGlobal      0
+0005 OpAdd
+0006 OpReturnValue
+";
+	assert_eq!(disassemble(&module(true)), expected);
+}
+
+#[test]
+fn listing_without_debug_info_has_no_source() {
+	let listing = disassemble(&module(false));
+	assert!(listing.starts_with("== main: <unknown source> ==\n0000 OpConstant"));
+	assert!(!listing.contains(" | "));
+	let annotated = disassemble(&module(true));
+	let without_source: Vec<&str> = annotated.lines().filter(|l| !l.contains(" | ")).collect();
+	assert_eq!(
+		listing.lines().skip(1).collect::<Vec<_>>(),
+		without_source[1..]
+	);
+}
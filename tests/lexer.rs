@@ -0,0 +1,92 @@
+mod common;
+
+use monkey_compiler::lexer::Lexer;
+use monkey_compiler::token::{Token, TokenType};
+
+// each token as (line, column, the source text its byte span covers)
+fn spans(src: &str) -> Vec<(usize, usize, &str)> {
+	Lexer::new(src.chars())
+		.map(|t| (t.span.line, t.span.column, &src[t.span.start..t.span.end]))
+		.collect()
+}
+
+#[test]
+fn tokens_carry_line_column_and_byte_span() {
+	assert_eq!(
+		spans("let é = 10;\n  \"héllo\" + x"),
+		[
+			(1, 1, "let"),
+			(1, 5, "é"),
+			(1, 7, "="),
+			(1, 9, "10"),
+			(1, 11, ";"),
+			(2, 3, "\"héllo\""),
+			(2, 11, "+"),
+			(2, 13, "x"),
+		]
+	);
+}
+
+#[test]
+fn parser_errors_point_at_the_offending_token() {
+	let src = "let a = 1;\nlet = 5;";
+	let program = common::parse(src);
+	let span = program.errors[0].get_span();
+	assert_eq!((span.line, span.column), (2, 5));
+	assert_eq!(&src[span.start..span.end], "=");
+}
+
+fn kinds(lexer: impl Iterator<Item = Token>) -> Vec<(TokenType, String)> {
+	lexer.map(|t| (t.token_type, t.literal)).collect()
+}
+
+#[test]
+fn comments_are_skipped_unless_trivia_is_kept() {
+	let src = "a; // one\n/* outer /* inner */ still */ b;";
+	let plain = kinds(Lexer::new(src.chars()));
+	let names: Vec<&str> = plain.iter().map(|(_, l)| l.as_str()).collect();
+	assert_eq!(names, ["a", ";", "b", ";"]);
+
+	let comments: Vec<String> = kinds(Lexer::new(src.chars()).with_trivia())
+		.into_iter()
+		.filter(|(t, _)| *t == TokenType::Comment)
+		.map(|(_, l)| l)
+		.collect();
+	assert_eq!(comments, ["// one", "/* outer /* inner */ still */"]);
+}
+
+#[test]
+fn unterminated_block_comments_are_errors() {
+	let tokens = kinds(Lexer::new("a; /* open /* nested */".chars()));
+	assert_eq!(
+		tokens.last(),
+		Some(&(TokenType::Error, String::from("Unterminated block comment")))
+	);
+}
+
+#[test]
+fn string_escapes_are_decoded() {
+	let tokens = kinds(Lexer::new(r#""a\tb\n\\\"\u{e9}\u{1F600}\r""#.chars()));
+	assert_eq!(
+		tokens,
+		[(TokenType::String, String::from("a\tb\n\\\"é😀\r"))]
+	);
+}
+
+#[test]
+fn bad_escapes_and_unterminated_strings_are_errors() {
+	let errors: Vec<String> = kinds(Lexer::new(r#""\q"; "\u{zz}"; "\u{110000}"; "open"#.chars()))
+		.into_iter()
+		.filter(|(t, _)| *t == TokenType::Error)
+		.map(|(_, l)| l)
+		.collect();
+	assert_eq!(
+		errors,
+		[
+			"Invalid escape sequence \\q",
+			"Invalid escape sequence \\u",
+			"Invalid escape sequence \\u",
+			"Unterminated string",
+		]
+	);
+}
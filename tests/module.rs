@@ -0,0 +1,106 @@
+mod common;
+
+use common::compile;
+use monkey_compiler::module::{self, DebugInfo, Module};
+use monkey_compiler::vm::Vm;
+
+const SOURCE: &str = "let big = 9223372036854775807 + 1;
+let add = fn(a) { fn(b) { a + b; }; };
+let xs = [1.5, \"two\", {\"k\": true}];
+return [add(big)(1), xs, len(\"héllo\")];";
+
+fn module(debug: bool) -> Module {
+	Module {
+		bytecode: compile(SOURCE),
+		debug: debug.then(|| DebugInfo {
+			source_name: String::from("test.mk"),
+			source: String::from(SOURCE),
+		}),
+	}
+}
+
+fn run(module: Module) -> String {
+	match Vm::new(module.bytecode).run() {
+		Ok(v) => v.inspect_obj(),
+		Err(e) => format!("{}: {}", e.get_err_type(), e.get_err_msg()),
+	}
+}
+
+fn decode_error(bytes: &[u8]) -> String {
+	match module::decode(bytes) {
+		Ok(_) => panic!("decoded a module that should have been rejected"),
+		Err(e) => e.get_err_type(),
+	}
+}
+
+#[test]
+fn round_trip_keeps_bytecode_and_debug_info() {
+	for debug in [true, false] {
+		let original = module(debug);
+		let bytes = module::encode(&original).unwrap();
+		assert!(module::is_module(&bytes));
+		let decoded = module::decode(&bytes).unwrap();
+
+		assert_eq!(
+			decoded.bytecode.instructions,
+			original.bytecode.instructions
+		);
+		assert_eq!(decoded.bytecode.num_locals, original.bytecode.num_locals);
+		assert_eq!(
+			decoded.bytecode.constants.len(),
+			original.bytecode.constants.len()
+		);
+		match (&decoded.debug, debug) {
+			(Some(d), true) => {
+				assert_eq!(d.source_name, "test.mk");
+				assert_eq!(d.source, SOURCE);
+				assert_eq!(decoded.bytecode.lines, original.bytecode.lines);
+			}
+			(None, false) => assert!(decoded.bytecode.lines.is_empty()),
+			_ => panic!("debug info was not kept as encoded"),
+		}
+		assert_eq!(run(decoded), run(module(debug)));
+	}
+}
+
+#[test]
+fn source_files_are_not_modules() {
+	assert!(!module::is_module(SOURCE.as_bytes()));
+	assert_eq!(decode_error(SOURCE.as_bytes()), "BadMagic");
+}
+
+#[test]
+fn truncated_modules_are_rejected() {
+	let bytes = module::encode(&module(true)).unwrap();
+	for len in 0..bytes.len() {
+		assert!(
+			module::decode(&bytes[..len]).is_err(),
+			"decoded a module cut to {} of {} bytes",
+			len,
+			bytes.len()
+		);
+	}
+}
+
+#[test]
+fn corrupt_modules_are_rejected() {
+	let bytes = module::encode(&module(true)).unwrap();
+
+	let mut version = bytes.clone();
+	version[4] = version[4].wrapping_add(1);
+	assert_eq!(decode_error(&version), "UnsupportedVersion");
+
+	let mut flags = bytes.clone();
+	flags[6] |= 0x80;
+	assert_eq!(decode_error(&flags), "Corrupt");
+
+	for pos in 11..bytes.len() {
+		let mut flipped = bytes.clone();
+		flipped[pos] ^= 0x01;
+		assert_eq!(decode_error(&flipped), "Corrupt", "flipped byte {}", pos);
+	}
+
+	let mut trailing = bytes;
+	trailing.push(0);
+	assert_eq!(decode_error(&trailing), "Corrupt");
+}
@@ -0,0 +1,66 @@
+use monkey_compiler::object::bigint::BigInt;
+use monkey_compiler::object::{integer_obj, Array, HashKey, ObjType, Value};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[test]
+fn values_report_their_type_and_print() {
+	let array = Value::Array(Array::new(VecDeque::from([
+		Value::Integer(1),
+		Value::String("a".into()),
+	])));
+	let cases = [
+		(Value::Integer(-3), ObjType::Integer, "-3"),
+		(Value::Float(2.0), ObjType::Float, "2.0"),
+		(Value::Boolean(true), ObjType::Boolean, "true"),
+		(Value::String("hi".into()), ObjType::String, "hi"),
+		(Value::Range(1, 4), ObjType::Range, "range(1, 4)"),
+		(Value::Null, ObjType::Null, "null"),
+		(array, ObjType::Array, "[, 1, a]"),
+	];
+	for (value, t, printed) in cases {
+		assert!(value.get_type() == t, "{}", printed);
+		assert_eq!(value.inspect_obj(), printed);
+	}
+}
+
+#[test]
+fn integers_only_stay_big_outside_the_i64_range() {
+	let max = BigInt::from_i64(i64::MAX);
+	assert!(matches!(integer_obj(max.clone()), Value::Integer(i64::MAX)));
+	let big = max.add(&BigInt::from_i64(1));
+	assert!(matches!(integer_obj(big.clone()), Value::BigInteger(_)));
+	assert!(matches!(
+		integer_obj(big.sub(&BigInt::from_i64(1))),
+		Value::Integer(i64::MAX)
+	));
+}
+
+#[test]
+fn hash_keys_compare_real_values() {
+	let key = |v: Value| v.hash_key().expect("hashable");
+	assert!(key(Value::Integer(1)) == key(Value::Integer(1)));
+	assert!(key(Value::Integer(1)) != key(Value::Boolean(true)));
+	assert!(key(Value::String("1".into())) != key(Value::Integer(1)));
+	assert!(key(Value::String("ab".into())) == key(Value::String("ab".into())));
+
+	let big = BigInt::from_i64(i64::MAX).mul(&BigInt::from_i64(4));
+	let other = BigInt::from_i64(i64::MAX).mul(&BigInt::from_i64(5));
+	let big_key = key(Value::BigInteger(Rc::new(big.clone())));
+	assert!(big_key == key(Value::BigInteger(Rc::new(big))));
+	assert!(big_key != key(Value::BigInteger(Rc::new(other))));
+	assert!(matches!(big_key, HashKey::BigInteger(_)));
+
+	assert!(Value::Float(1.0).hash_key().is_none());
+	assert!(Value::Array(Array::new(VecDeque::new()))
+		.hash_key()
+		.is_none());
+}
+
+#[test]
+fn arrays_are_shared_between_clones() {
+	let a = Array::new(VecDeque::from([Value::Integer(1)]));
+	let b = a.clone();
+	b.mems.borrow_mut().push_back(Value::Integer(2));
+	assert_eq!(Value::Array(a).inspect_obj(), "[, 1, 2]");
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::parse;
+use monkey_compiler::ast::{Expr, Node, Stmt};
+
+fn error_lines(src: &str) -> Vec<(usize, usize)> {
+	parse(src)
+		.errors
+		.iter()
+		.map(|e| (e.get_span().line, e.get_span().column))
+		.collect()
+}
+
+#[test]
+fn every_syntax_error_is_reported() {
+	let src = "let a = ;
+let b = 2;
+let c 3;
+fn(x { x };
+let d = [1, 2;
+return b;";
+	assert_eq!(error_lines(src), [(1, 9), (3, 7), (4, 6), (5, 14)]);
+}
+
+#[test]
+fn parsing_resumes_after_an_error() {
+	let program = parse("let a = ;\nlet b = 2;\nlet c 3;\nreturn b;");
+	let names: Vec<&str> = program
+		.statements
+		.iter()
+		.map(|s| match s {
+			Stmt::Let(l) => l.name.value.as_str(),
+			Stmt::Return(_) => "return",
+			_ => "other",
+		})
+		.collect();
+	assert_eq!(names, ["b", "return"]);
+}
+
+#[test]
+fn errors_inside_blocks_resume_at_the_next_statement() {
+	let src = "let f = fn() {\n  let = 1;\n  2;\n};\nlet g = fn() { 3 +; };\nlet ok = 1;";
+	assert_eq!(error_lines(src), [(2, 7), (5, 19)]);
+	// a statement with an error anywhere inside it is dropped
+	assert_eq!(parse(src).statements.len(), 1);
+}
+
+#[test]
+fn both_backends_report_the_first_syntax_error() {
+	common::check(&[(
+		"let a = ;\nlet b 2;",
+		"ParserError: no prefix parse function for ; Token",
+	)]);
+}
+
+fn printed(src: &str) -> String {
+	let program = parse(src);
+	assert!(program.errors.is_empty(), "{}", src);
+	program.string()
+}
+
+#[test]
+fn operator_precedence_is_kept_in_the_tree() {
+	let cases = [
+		("-a * b + c;", "(((-a)*b)+c)"),
+		("a + b * c <= d || e && !f;", "(((a+(b*c))<=d)||(e&&(!f)))"),
+		("a = b += 1;", "(a = (b += 1))"),
+	];
+	for (src, expected) in cases {
+		assert_eq!(printed(src).trim(), expected, "{}", src);
+	}
+}
+
+#[test]
+fn nodes_are_plain_enums() {
+	let program = parse("let f = fn(x) { x * 2; }; f(3);");
+	match &program.statements[..] {
+		[Stmt::Let(l), Stmt::Expression(e)] => {
+			assert_eq!(l.name.value, "f");
+			match &l.value {
+				Expr::Function(f) => {
+					assert_eq!(f.params.len(), 1);
+					assert!(matches!(
+						&f.body.statements[..],
+						[Stmt::Expression(s)] if matches!(&s.expression, Expr::Infix(i) if i.operator == "*")
+					));
+				}
+				other => panic!("expected a function, got {:?}", other),
+			}
+			assert!(matches!(&e.expression, Expr::Call(c) if c.args.len() == 1));
+		}
+		other => panic!("unexpected statements {:?}", other),
+	}
+	// the same source parses to an equal tree, spans included
+	assert_eq!(parse("let f = fn(x) { x * 2; }; f(3);"), program);
+	assert_ne!(parse("let f = fn(x) { x * 3; }; f(3);"), program);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::check;
+use monkey_compiler::compiler::symbol_table::{SymbolScope, SymbolTable};
+
+fn resolved(table: &mut SymbolTable, name: &str) -> (SymbolScope, usize) {
+	let symbol = table.resolve(name).expect("name should resolve");
+	(symbol.scope, symbol.index)
+}
+
+#[test]
+fn globals_locals_and_builtins() {
+	let mut global = SymbolTable::new();
+	global.define(String::from("a"));
+	global.define(String::from("b"));
+	assert_eq!(resolved(&mut global, "b"), (SymbolScope::Global, 1));
+	assert_eq!(resolved(&mut global, "len"), (SymbolScope::Builtin, 0));
+	assert!(global.resolve("missing").is_none());
+
+	let mut local = SymbolTable::new_enclosed(global);
+	local.define(String::from("c"));
+	assert_eq!(resolved(&mut local, "a"), (SymbolScope::Global, 0));
+	assert_eq!(resolved(&mut local, "c"), (SymbolScope::Local, 0));
+	assert!(local.free_symbols.is_empty());
+}
+
+#[test]
+fn outer_locals_become_free() {
+	let mut global = SymbolTable::new();
+	global.define(String::from("a"));
+	let mut first = SymbolTable::new_enclosed(global);
+	first.define(String::from("b"));
+	first.define(String::from("c"));
+	let mut second = SymbolTable::new_enclosed(first);
+	second.define(String::from("d"));
+
+	assert_eq!(resolved(&mut second, "a"), (SymbolScope::Global, 0));
+	assert_eq!(resolved(&mut second, "c"), (SymbolScope::Free, 0));
+	assert_eq!(resolved(&mut second, "b"), (SymbolScope::Free, 1));
+	assert_eq!(resolved(&mut second, "c"), (SymbolScope::Free, 0));
+	assert_eq!(resolved(&mut second, "d"), (SymbolScope::Local, 0));
+	let free: Vec<(&str, SymbolScope, usize)> = second
+		.free_symbols
+		.iter()
+		.map(|s| (s.name.as_str(), s.scope, s.index))
+		.collect();
+	assert_eq!(
+		free,
+		[("c", SymbolScope::Local, 1), ("b", SymbolScope::Local, 0)]
+	);
+}
+
+#[test]
+fn closures_capture_through_every_level() {
+	check(&[
+		(
+			"let add = fn(a) { fn(b) { a + b; }; }; let inc = add(2); return inc(3);",
+			"5",
+		),
+		(
+			"let f = fn(a) { fn(b) { fn(c) { a + b + c; }; }; }; return f(1)(2)(3);",
+			"6",
+		),
+		(
+			"let g = 10; let f = fn(a) { let b = a * 2; fn() { g + a + b; }; }; return f(1)();",
+			"13",
+		),
+		(
+			"let fib = fn(n) { if (n < 2) { n; } else { fib(n - 1) + fib(n - 2); }; }; return fib(15);",
+			"610",
+		),
+		("let len = 1; return len(\"abc\");", "3"),
+	]);
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::parse;
+use monkey_compiler::ast::fold::{self, Folder};
+use monkey_compiler::ast::visit::{self, Visitor};
+use monkey_compiler::ast::{
+	CallExpression, Expr, Identifier, InfixExpression, IntegerLiteral, Node, Program,
+};
+use monkey_compiler::eval::eval_node;
+use monkey_compiler::object::Env;
+use monkey_compiler::token::TokenType;
+
+#[derive(Default)]
+struct Counter {
+	declared: Vec<String>,
+	read: Vec<String>,
+	calls: usize,
+	integers: usize,
+}
+
+impl Visitor for Counter {
+	fn visit_identifier(&mut self, n: &Identifier) {
+		self.declared.push(n.value.clone());
+	}
+	fn visit_identifier_expr(&mut self, n: &Identifier) {
+		self.read.push(n.value.clone());
+	}
+	fn visit_integer(&mut self, _n: &IntegerLiteral) {
+		self.integers += 1;
+	}
+	fn visit_call(&mut self, n: &CallExpression) {
+		self.calls += 1;
+		visit::walk_call(self, n)
+	}
+}
+
+#[test]
+fn visitor_reaches_every_node() {
+	let program = parse(
+		"let add = fn(a, b) { a + b; };
+for (i, x in [1, 2]) { add(i, x); };
+while (add(1, 2) > 5) { break; };
+let h = {\"k\": if (true) { 3; } else { 4; }};
+h = h;",
+	);
+	let mut counter = Counter::default();
+	counter.visit_program(&program);
+	assert_eq!(counter.declared, ["add", "a", "b", "i", "x", "h", "h"]);
+	assert_eq!(counter.read, ["a", "b", "add", "i", "x", "add", "h"]);
+	assert_eq!(counter.calls, 2);
+	assert_eq!(counter.integers, 7);
+}
+
+// folds infix arithmetic on integer literals into a single literal
+struct ConstantFolder;
+
+impl Folder for ConstantFolder {
+	fn fold_infix(&mut self, n: InfixExpression) -> Expr {
+		let n = fold::fold_infix(self, n);
+		let value = match (n.left.as_ref(), n.operator.as_str(), n.right.as_ref()) {
+			(Expr::Integer(l), "+", Expr::Integer(r)) => l.value.checked_add(r.value),
+			(Expr::Integer(l), "*", Expr::Integer(r)) => l.value.checked_mul(r.value),
+			_ => None,
+		};
+		match value {
+			Some(value) => {
+				let mut token = n.token;
+				token.token_type = TokenType::Int;
+				token.literal = value.to_string();
+				Expr::Integer(IntegerLiteral { token, value })
+			}
+			None => Expr::Infix(n),
+		}
+	}
+}
+
+fn folded(src: &str, folder: &mut impl Folder) -> Program {
+	let program = parse(src);
+	assert!(program.errors.is_empty(), "{}", src);
+	folder.fold_program(program)
+}
+
+#[test]
+fn folder_replaces_constant_arithmetic() {
+	let program = folded("1 + 2;", &mut ConstantFolder);
+	assert_eq!(program.string().trim(), "3");
+
+	let program = folded(
+		"let f = fn(x) { x + 2 * 3; }; return f(1 + 1);",
+		&mut ConstantFolder,
+	);
+	let expected = parse("let f = fn(x) { x + 6; }; return f(2);");
+	assert_eq!(program.string(), expected.string());
+	let result = eval_node(&program, &mut Env::new(None)).unwrap();
+	assert_eq!(result.inspect_obj(), "8");
+}
+
+// replaces reads of one name with a literal, declarations keep the name
+struct Inline(&'static str, i64);
+
+impl Folder for Inline {
+	fn fold_identifier_expr(&mut self, n: Identifier) -> Expr {
+		match n.value == self.0 {
+			true => {
+				let mut token = n.token;
+				token.token_type = TokenType::Int;
+				token.literal = self.1.to_string();
+				Expr::Integer(IntegerLiteral {
+					token,
+					value: self.1,
+				})
+			}
+			false => Expr::Identifier(n),
+		}
+	}
+}
+
+#[test]
+fn identifier_reads_can_become_expressions() {
+	let program = folded("let x = 1; x = 2; return x + x;", &mut Inline("x", 20));
+	let result = eval_node(&program, &mut Env::new(None)).unwrap();
+	assert_eq!(result.inspect_obj(), "40");
+}